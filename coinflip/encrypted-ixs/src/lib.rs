@@ -4,6 +4,12 @@ use arcis::*;
 mod circuits {
     use arcis::*;
 
+    /// Number of faces on the die used by `roll_dice`.
+    const DICE_SIDES: u8 = 100;
+
+    /// Number of flips resolved by a single `flip_batch` computation.
+    const FLIP_BATCH_SIZE: usize = 8;
+
     /// Represents the player's choice in the coin flip game.
     pub struct UserChoice {
         pub choice: bool, // Player's choice: true for heads, false for tails
@@ -32,4 +38,105 @@ mod circuits {
         // Compare player's choice with the coin flip result and reveal only the outcome
         (input.choice == toss).reveal()
     }
+
+    /// Performs a confidential coin flip whose outcome only the player can read.
+    ///
+    /// Identical to `flip`, except the result is encrypted to the player's key instead of
+    /// being revealed, so observers of the chain learn nothing about the outcome.
+    ///
+    /// # Arguments
+    /// * `input_ctxt` - Player's encrypted choice (heads or tails)
+    /// * `player` - Player's key the result is encrypted to
+    ///
+    /// # Returns
+    /// * Encrypted `true` if the player's choice matches the coin flip, `false` otherwise
+    #[instruction]
+    pub fn flip_private(input_ctxt: Enc<Shared, UserChoice>, player: Shared) -> Enc<Shared, bool> {
+        let input = input_ctxt.to_arcis();
+
+        let toss = ArcisRNG::bool();
+
+        player.from_arcis(input.choice == toss)
+    }
+
+    /// Performs several confidential coin flips in one computation.
+    ///
+    /// Each of the player's encrypted choices is compared with its own random bit, so a
+    /// batch costs one MPC round trip instead of one per flip. Only the outcomes are
+    /// revealed, packed into a bitmap.
+    ///
+    /// # Arguments
+    /// * `input_ctxt` - Player's encrypted choices, one per flip
+    ///
+    /// # Returns
+    /// * Bitmap of outcomes, where bit `i` is set if the player won flip `i`
+    #[instruction]
+    pub fn flip_batch(input_ctxt: Enc<Shared, [UserChoice; FLIP_BATCH_SIZE]>) -> u8 {
+        let input = input_ctxt.to_arcis();
+
+        let mut wins: u8 = 0;
+        for i in 0..FLIP_BATCH_SIZE {
+            let toss = ArcisRNG::bool();
+            let bit: u8 = if input[i].choice == toss { 1 << i } else { 0 };
+            wins = wins + bit;
+        }
+
+        wins.reveal()
+    }
+
+    /// Represents the player's chosen faces in a dice roll.
+    pub struct DiceChoice {
+        pub target: u8, // First winning face, in 0..DICE_SIDES
+    }
+
+    /// Rolls a 100-sided die and checks whether it lands in the player's winning window.
+    ///
+    /// The player wins if the roll falls in the `win_count` faces starting at their
+    /// encrypted `target`, wrapping around past the last face. "Roll under N" is
+    /// `target = 0, win_count = N`; a plain coin flip is `win_count = 50`. The window size
+    /// is public so the program can price the bet, while the faces it covers stay hidden.
+    ///
+    /// The roll is built from 7 random bits per draw and rejected if it is not a valid
+    /// face, like the house move in rock-paper-scissors. Each draw is accepted with
+    /// probability 100/128, so 16 draws leave a negligible chance of no valid roll.
+    ///
+    /// # Arguments
+    /// * `input_ctxt` - Player's encrypted first winning face
+    /// * `win_count` - Number of winning faces (1 to DICE_SIDES - 1)
+    ///
+    /// # Returns
+    /// * `true` if the roll lands in the player's window (player wins)
+    /// * `false` otherwise, including when `target` is not a valid face
+    #[instruction]
+    pub fn roll_dice(input_ctxt: Enc<Shared, DiceChoice>, win_count: u8) -> bool {
+        let input = input_ctxt.to_arcis();
+
+        let mut roll: u8 = 0;
+        let mut selected = false;
+
+        for _ in 0..16 {
+            let mut candidate: u8 = 0;
+            for _ in 0..7 {
+                let bit: u8 = if ArcisRNG::bool() { 1 } else { 0 };
+                candidate = candidate * 2 + bit;
+            }
+
+            let candidate_valid = candidate < DICE_SIDES;
+            let take = (!selected) & candidate_valid;
+
+            roll = if take { candidate } else { roll };
+            selected = selected | candidate_valid;
+        }
+
+        // Distance from the first winning face to the roll, wrapping around the die
+        let distance = if roll >= input.target {
+            roll - input.target
+        } else {
+            roll + DICE_SIDES - input.target
+        };
+
+        let won = input.target < DICE_SIDES && distance < win_count;
+
+        won.reveal()
+    }
 }
@@ -1,7 +1,27 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
 use arcium_anchor::prelude::*;
+use arcium_client::idl::arcium::types::CallbackAccount;
 
 const COMP_DEF_OFFSET_FLIP: u32 = comp_def_offset("flip");
+const COMP_DEF_OFFSET_ROLL_DICE: u32 = comp_def_offset("roll_dice");
+const COMP_DEF_OFFSET_FLIP_BATCH: u32 = comp_def_offset("flip_batch");
+const COMP_DEF_OFFSET_FLIP_PRIVATE: u32 = comp_def_offset("flip_private");
+
+/// Number of flips resolved by a single `flip_batch` computation.
+///
+/// Each encrypted choice is a 32-byte ciphertext in the instruction data, so the batch
+/// size is bounded by the transaction size limit.
+const FLIP_BATCH_SIZE: usize = 8;
+
+/// Number of faces on the die rolled by the `roll_dice` circuit.
+const DICE_SIDES: u8 = 100;
+
+/// Denominator for the house edge, expressed in basis points.
+const BASIS_POINTS: u64 = 10_000;
+/// Upper bound on the configurable house edge (10%).
+const MAX_HOUSE_EDGE_BPS: u16 = 1_000;
 
 declare_id!("AyXE8Npj6s3e74XhUoLu8WmnBGPfUcAjzG8oSyYBbnvP");
 
@@ -16,77 +36,1706 @@ pub mod coinflip {
         Ok(())
     }
 
-    /// Initiates a coin flip game with the player's encrypted choice.
+    /// Creates the house treasury and the wager vault.
+    ///
+    /// The house account doubles as the treasury: it holds the lamports used to pay out
+    /// winnings and collects lost stakes. The vault only ever holds stakes of flips that
+    /// are still waiting on their MPC result.
+    ///
+    /// # Arguments
+    /// * `house_edge_bps` - House edge in basis points, taken from the fair payout on a win
+    pub fn initialize_house(ctx: Context<InitializeHouse>, house_edge_bps: u16) -> Result<()> {
+        require!(
+            house_edge_bps <= MAX_HOUSE_EDGE_BPS,
+            ErrorCode::InvalidHouseEdge
+        );
+
+        let house = &mut ctx.accounts.house;
+        house.bump = ctx.bumps.house;
+        house.authority = ctx.accounts.authority.key();
+        house.house_edge_bps = house_edge_bps;
+        house.reserved = 0;
+
+        ctx.accounts.vault.bump = ctx.bumps.vault;
+
+        Ok(())
+    }
+
+    /// Updates the house edge applied to future flips.
+    pub fn set_house_edge(ctx: Context<UpdateHouse>, house_edge_bps: u16) -> Result<()> {
+        require!(
+            house_edge_bps <= MAX_HOUSE_EDGE_BPS,
+            ErrorCode::InvalidHouseEdge
+        );
+        ctx.accounts.house.house_edge_bps = house_edge_bps;
+        Ok(())
+    }
+
+    /// Deposits lamports into the house treasury so it can cover winning flips.
+    pub fn fund_house(ctx: Context<FundHouse>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: ctx.accounts.house.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        Ok(())
+    }
+
+    /// Withdraws lamports from the house treasury to the authority.
+    ///
+    /// Lamports reserved for pending flips and the rent-exempt minimum stay in the treasury.
+    pub fn withdraw_house(ctx: Context<UpdateHouse>, amount: u64) -> Result<()> {
+        let available = available_house_lamports(&ctx.accounts.house)?;
+        require!(amount <= available, ErrorCode::InsufficientHouseFunds);
+
+        ctx.accounts.house.sub_lamports(amount)?;
+        ctx.accounts.authority.add_lamports(amount)?;
+        Ok(())
+    }
+
+    /// Creates the treasury and wager vault for flips wagered in `mint`.
+    ///
+    /// Both are token accounts owned by the `TokenHouse` PDA. The treasury is funded with a
+    /// plain token transfer, and the house edge is the one set on the SOL house.
+    pub fn initialize_token_house(ctx: Context<InitializeTokenHouse>) -> Result<()> {
+        let token_house = &mut ctx.accounts.token_house;
+        token_house.bump = ctx.bumps.token_house;
+        token_house.mint = ctx.accounts.mint.key();
+        token_house.vault = ctx.accounts.token_vault.key();
+        token_house.treasury = ctx.accounts.token_treasury.key();
+        token_house.reserved = 0;
+        Ok(())
+    }
+
+    /// Withdraws tokens from a token treasury to an account of the authority's choosing.
+    ///
+    /// Tokens reserved for pending flips stay in the treasury.
+    pub fn withdraw_token_house(ctx: Context<WithdrawTokenHouse>, amount: u64) -> Result<()> {
+        let token_house = &ctx.accounts.token_house;
+        let available = ctx
+            .accounts
+            .token_treasury
+            .amount
+            .saturating_sub(token_house.reserved);
+        require!(amount <= available, ErrorCode::InsufficientHouseFunds);
+
+        transfer_from_token_house(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_house,
+            &ctx.accounts.token_treasury,
+            &ctx.accounts.destination,
+            amount,
+        )
+    }
+
+    /// Initiates a coin flip game with the player's encrypted choice and a SOL or SPL token
+    /// wager.
     ///
     /// The player submits their choice (heads or tails) in encrypted form along with their
-    /// public key and nonce. The MPC computation will generate a cryptographically secure
-    /// random boolean and compare it with the player's choice to determine if they won.
+    /// public key and nonce. The wager is moved into the vault and the house reserves the
+    /// lamports it would owe on a win, so the payout is guaranteed to be available when the
+    /// MPC computation completes. A `FlipBet` account records the flip as pending until the
+    /// callback settles it.
+    ///
+    /// Passing the `mint` and token accounts of an initialized `TokenHouse` wagers that token
+    /// instead: the stake moves from `player_token_account` into the token vault and the
+    /// payout is reserved in the token treasury.
     ///
     /// # Arguments
+    /// * `bet_nonce` - Player-chosen identifier that keys this bet's `FlipBet` account
     /// * `user_choice` - Player's encrypted choice (true for heads, false for tails)
     /// * `pub_key` - Player's public key for encryption operations
     /// * `nonce` - Cryptographic nonce for the encryption
+    /// * `wager` - Stake in lamports, or in the token's base units for a token wager
     pub fn flip(
         ctx: Context<Flip>,
         computation_offset: u64,
+        bet_nonce: u64,
+        user_choice: [u8; 32],
+        pub_key: [u8; 32],
+        nonce: u128,
+        wager: u64,
+    ) -> Result<()> {
+        let mint = ctx.accounts.mint.as_ref().map(|mint| mint.key());
+        let payout = match mint {
+            Some(_) => ctx.accounts.escrow_token_wager(wager)?,
+            None => {
+                // A coin flip wins on one of two equally likely outcomes
+                let payout = reserve_payout(&mut ctx.accounts.house, wager, 1, 2)?;
+                deposit_wager(
+                    &ctx.accounts.payer,
+                    &ctx.accounts.vault,
+                    &ctx.accounts.system_program,
+                    wager,
+                )?;
+                payout
+            }
+        };
+
+        ctx.accounts.flip_bet.open(
+            ctx.bumps.flip_bet,
+            ctx.accounts.payer.key(),
+            bet_nonce,
+            computation_offset,
+            1,
+            mint,
+            wager,
+            payout,
+        )?;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_u8(user_choice)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let mut callback_accounts = vec![
+            CallbackAccount {
+                pubkey: ctx.accounts.flip_bet.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.vault.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.house.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.payer.key(),
+                is_writable: true,
+            },
+        ];
+        // The token accounts follow, with the program id standing in for the absent ones of
+        // a SOL wager
+        let accounts = &ctx.accounts;
+        callback_accounts.extend(
+            [
+                (accounts.mint.as_ref().map(|a| a.key()), false),
+                (accounts.token_house.as_ref().map(|a| a.key()), true),
+                (accounts.token_vault.as_ref().map(|a| a.key()), true),
+                (accounts.token_treasury.as_ref().map(|a| a.key()), true),
+                (
+                    accounts.player_token_account.as_ref().map(|a| a.key()),
+                    true,
+                ),
+                (accounts.token_program.as_ref().map(|a| a.key()), false),
+            ]
+            .map(|(account, is_writable)| CallbackAccount {
+                pubkey: account.unwrap_or(crate::ID),
+                is_writable: is_writable && account.is_some(),
+            }),
+        );
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![FlipCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &callback_accounts,
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Handles the result of the coin flip MPC computation and settles the wager.
+    ///
+    /// This callback receives the result of comparing the player's choice with the
+    /// randomly generated coin flip. On a win the player receives 2x the stake minus the
+    /// house edge, on a loss the stake is swept into the house treasury. If the computation
+    /// was aborted the stake is refunded instead, so funds never stay stranded in the vault.
+    /// Token wagers settle the same way through the token vault and treasury.
+    /// The outcome is also written to the `FlipBet` account so it can be read from chain state.
+    #[arcium_callback(encrypted_ix = "flip")]
+    pub fn flip_callback(
+        ctx: Context<FlipCallback>,
+        output: SignedComputationOutputs<FlipOutput>,
+    ) -> Result<()> {
+        let result = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(FlipOutput { field_0 }) => Some(field_0),
+            Err(_) => None,
+        };
+
+        let paid_out = match ctx.accounts.flip_bet.mint {
+            Some(_) => ctx.accounts.settle_token_bet(result.map(u8::from))?,
+            None => settle_bet(
+                &mut ctx.accounts.flip_bet,
+                &ctx.accounts.vault,
+                &mut ctx.accounts.house,
+                &ctx.accounts.player,
+                result.map(u8::from),
+            )?,
+        };
+
+        let flip_bet = &ctx.accounts.flip_bet;
+        match result {
+            Some(result) => emit!(FlipEvent {
+                flip_bet: flip_bet.key(),
+                result,
+                player: flip_bet.player,
+                wager: flip_bet.wager,
+                payout: paid_out,
+            }),
+            None => emit!(FlipRefundedEvent {
+                flip_bet: flip_bet.key(),
+                player: flip_bet.player,
+                wager: flip_bet.wager,
+            }),
+        }
+
+        Ok(())
+    }
+
+    /// Initializes the computation definition for the private coin flip operation.
+    pub fn init_flip_private_comp_def(ctx: Context<InitFlipPrivateCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initiates a coin flip whose outcome is delivered encrypted to the player.
+    ///
+    /// Unlike `flip`, the result is never revealed on chain: the MPC computation encrypts it
+    /// to the player's x25519 key. Because settling a wager would expose the outcome through
+    /// the transferred lamports, private flips carry no stake and no `FlipBet` account. A
+    /// short-lived `PrivateFlip` account remembers who queued the flip so the callback can
+    /// attribute the result, and is closed back to the player once it is delivered.
+    ///
+    /// # Arguments
+    /// * `user_choice` - Player's encrypted choice (true for heads, false for tails)
+    /// * `pub_key` - Player's public key, used for the choice and for the encrypted result
+    /// * `nonce` - Cryptographic nonce for the choice encryption
+    /// * `result_nonce` - Cryptographic nonce for the result encryption
+    pub fn flip_private(
+        ctx: Context<FlipPrivate>,
+        computation_offset: u64,
         user_choice: [u8; 32],
         pub_key: [u8; 32],
         nonce: u128,
+        result_nonce: u128,
     ) -> Result<()> {
+        let private_flip = &mut ctx.accounts.private_flip;
+        private_flip.bump = ctx.bumps.private_flip;
+        private_flip.player = ctx.accounts.payer.key();
+        private_flip.computation_offset = computation_offset;
+
         let args = ArgBuilder::new()
             .x25519_pubkey(pub_key)
             .plaintext_u128(nonce)
             .encrypted_u8(user_choice)
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(result_nonce)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![FlipPrivateCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.private_flip.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.payer.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Handles the result of the private coin flip MPC computation.
+    ///
+    /// This callback receives the outcome encrypted to the player and emits the ciphertext
+    /// and nonce for the player to decrypt, along with the player and computation offset so
+    /// the event can be matched to the flip that requested it.
+    #[arcium_callback(encrypted_ix = "flip_private")]
+    pub fn flip_private_callback(
+        ctx: Context<FlipPrivateCallback>,
+        output: SignedComputationOutputs<FlipPrivateOutput>,
+    ) -> Result<()> {
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(FlipPrivateOutput { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let private_flip = &ctx.accounts.private_flip;
+        emit!(FlipPrivateEvent {
+            player: private_flip.player,
+            computation_offset: private_flip.computation_offset,
+            result: o.ciphertexts[0],
+            nonce: o.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    /// Initializes the computation definition for the batched coin flip operation.
+    pub fn init_flip_batch_comp_def(ctx: Context<InitFlipBatchCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initiates `FLIP_BATCH_SIZE` coin flips in a single MPC computation.
+    ///
+    /// Every flip carries the same stake, and the whole batch is escrowed and recorded in
+    /// one `FlipBet` account. Each winning flip pays 2x its stake minus the house edge.
+    ///
+    /// # Arguments
+    /// * `bet_nonce` - Player-chosen identifier that keys this bet's `FlipBet` account
+    /// * `user_choices` - Player's encrypted choices, one per flip
+    /// * `pub_key` - Player's public key for encryption operations
+    /// * `nonce` - Cryptographic nonce for the encryption
+    /// * `wager_per_flip` - Stake in lamports for each flip
+    pub fn flip_batch(
+        ctx: Context<FlipBatch>,
+        computation_offset: u64,
+        bet_nonce: u64,
+        user_choices: [[u8; 32]; FLIP_BATCH_SIZE],
+        pub_key: [u8; 32],
+        nonce: u128,
+        wager_per_flip: u64,
+    ) -> Result<()> {
+        let wager = wager_per_flip
+            .checked_mul(FLIP_BATCH_SIZE as u64)
+            .ok_or(ErrorCode::Overflow)?;
+        let payout = reserve_payout(&mut ctx.accounts.house, wager, 1, 2)?;
+
+        ctx.accounts.flip_bet.open(
+            ctx.bumps.flip_bet,
+            ctx.accounts.payer.key(),
+            bet_nonce,
+            computation_offset,
+            FLIP_BATCH_SIZE as u8,
+            None,
+            wager,
+            payout,
+        )?;
+
+        deposit_wager(
+            &ctx.accounts.payer,
+            &ctx.accounts.vault,
+            &ctx.accounts.system_program,
+            wager,
+        )?;
+
+        let mut builder = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce);
+        for choice in user_choices {
+            builder = builder.encrypted_u8(choice);
+        }
+        let args = builder.build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![FlipBatchCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.flip_bet.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.vault.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.house.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.payer.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Handles the result of the batched coin flip MPC computation and settles the wager.
+    ///
+    /// The revealed bitmap is decoded into one outcome per flip, in the order the choices
+    /// were submitted.
+    #[arcium_callback(encrypted_ix = "flip_batch")]
+    pub fn flip_batch_callback(
+        ctx: Context<FlipBatchCallback>,
+        output: SignedComputationOutputs<FlipBatchOutput>,
+    ) -> Result<()> {
+        let results = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(FlipBatchOutput { field_0 }) => Some(
+                (0..FLIP_BATCH_SIZE)
+                    .map(|i| field_0 & (1 << i) != 0)
+                    .collect::<Vec<bool>>(),
+            ),
+            Err(_) => None,
+        };
+
+        let wins = results
+            .as_ref()
+            .map(|results| results.iter().filter(|won| **won).count() as u8);
+
+        let paid_out = settle_bet(
+            &mut ctx.accounts.flip_bet,
+            &ctx.accounts.vault,
+            &mut ctx.accounts.house,
+            &ctx.accounts.player,
+            wins,
+        )?;
+
+        let flip_bet = &ctx.accounts.flip_bet;
+        match results {
+            Some(results) => emit!(FlipBatchEvent {
+                flip_bet: flip_bet.key(),
+                results,
+                player: flip_bet.player,
+                wager: flip_bet.wager,
+                payout: paid_out,
+            }),
+            None => emit!(FlipRefundedEvent {
+                flip_bet: flip_bet.key(),
+                player: flip_bet.player,
+                wager: flip_bet.wager,
+            }),
+        }
+
+        Ok(())
+    }
+
+    /// Initializes the computation definition for the dice roll operation.
+    pub fn init_roll_dice_comp_def(ctx: Context<InitRollDiceCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initiates a dice roll with the player's encrypted winning window and a SOL wager.
+    ///
+    /// The player wins if a 100-sided die lands on one of `win_count` consecutive faces
+    /// starting at their encrypted target. The window size is public, so the payout is
+    /// priced here at fair odds (`DICE_SIDES / win_count` times the wager) minus the
+    /// house edge. Escrow and settlement work exactly like `flip`.
+    ///
+    /// # Arguments
+    /// * `bet_nonce` - Player-chosen identifier that keys this bet's `FlipBet` account
+    /// * `target` - Player's encrypted first winning face
+    /// * `pub_key` - Player's public key for encryption operations
+    /// * `nonce` - Cryptographic nonce for the encryption
+    /// * `win_count` - Number of winning faces, between 1 and 99; odds whose payout after
+    ///   the edge would not exceed the wager are rejected
+    /// * `wager` - Stake in lamports
+    pub fn roll_dice(
+        ctx: Context<RollDice>,
+        computation_offset: u64,
+        bet_nonce: u64,
+        target: [u8; 32],
+        pub_key: [u8; 32],
+        nonce: u128,
+        win_count: u8,
+        wager: u64,
+    ) -> Result<()> {
+        require!(
+            win_count > 0 && win_count < DICE_SIDES,
+            ErrorCode::InvalidOdds
+        );
+
+        let payout = reserve_payout(
+            &mut ctx.accounts.house,
+            wager,
+            win_count as u64,
+            DICE_SIDES as u64,
+        )?;
+
+        ctx.accounts.flip_bet.open(
+            ctx.bumps.flip_bet,
+            ctx.accounts.payer.key(),
+            bet_nonce,
+            computation_offset,
+            1,
+            None,
+            wager,
+            payout,
+        )?;
+
+        deposit_wager(
+            &ctx.accounts.payer,
+            &ctx.accounts.vault,
+            &ctx.accounts.system_program,
+            wager,
+        )?;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_u8(target)
+            .plaintext_u8(win_count)
             .build();
 
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
-        queue_computation(
-            ctx.accounts,
-            computation_offset,
-            args,
-            vec![FlipCallback::callback_ix(
-                computation_offset,
-                &ctx.accounts.mxe_account,
-                &[],
-            )?],
-            1,
-            0,
-        )?;
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RollDiceCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.flip_bet.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.vault.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.house.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.payer.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Handles the result of the dice roll MPC computation and settles the wager.
+    ///
+    /// Only the win/lose outcome is revealed; the roll and the player's window stay hidden.
+    #[arcium_callback(encrypted_ix = "roll_dice")]
+    pub fn roll_dice_callback(
+        ctx: Context<RollDiceCallback>,
+        output: SignedComputationOutputs<RollDiceOutput>,
+    ) -> Result<()> {
+        let result = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RollDiceOutput { field_0 }) => Some(field_0),
+            Err(_) => None,
+        };
+
+        let paid_out = settle_bet(
+            &mut ctx.accounts.flip_bet,
+            &ctx.accounts.vault,
+            &mut ctx.accounts.house,
+            &ctx.accounts.player,
+            result.map(u8::from),
+        )?;
+
+        let flip_bet = &ctx.accounts.flip_bet;
+        match result {
+            Some(result) => emit!(DiceRollEvent {
+                flip_bet: flip_bet.key(),
+                result,
+                player: flip_bet.player,
+                wager: flip_bet.wager,
+                payout: paid_out,
+            }),
+            None => emit!(FlipRefundedEvent {
+                flip_bet: flip_bet.key(),
+                player: flip_bet.player,
+                wager: flip_bet.wager,
+            }),
+        }
+
+        Ok(())
+    }
+}
+
+/// Prices a bet that wins on `winning_outcomes` out of `total_outcomes` equally likely
+/// outcomes, returning its payout and the house's share of it.
+fn price_bet(
+    house: &House,
+    wager: u64,
+    winning_outcomes: u64,
+    total_outcomes: u64,
+) -> Result<(u64, u64)> {
+    require!(wager > 0, ErrorCode::InvalidWager);
+
+    let payout = house.payout_for(wager, winning_outcomes, total_outcomes)?;
+    // Odds so short that the edge eats the whole profit would pay a winner less than
+    // their stake
+    require!(payout > wager, ErrorCode::InvalidOdds);
+    Ok((payout, payout - wager))
+}
+
+/// Reserves the house's share of a winning payout so the treasury can always cover it.
+///
+/// Returns the payout for a bet that wins on `winning_outcomes` out of `total_outcomes`
+/// equally likely outcomes.
+fn reserve_payout(
+    house: &mut Account<House>,
+    wager: u64,
+    winning_outcomes: u64,
+    total_outcomes: u64,
+) -> Result<u64> {
+    let (payout, house_risk) = price_bet(house, wager, winning_outcomes, total_outcomes)?;
+    require!(
+        house_risk <= available_house_lamports(house)?,
+        ErrorCode::InsufficientHouseFunds
+    );
+    house.reserved = house
+        .reserved
+        .checked_add(house_risk)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok(payout)
+}
+
+/// Moves the player's stake into the vault.
+fn deposit_wager<'info>(
+    payer: &Signer<'info>,
+    vault: &Account<'info, Vault>,
+    system_program: &Program<'info, System>,
+    wager: u64,
+) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            system_program::Transfer {
+                from: payer.to_account_info(),
+                to: vault.to_account_info(),
+            },
+        ),
+        wager,
+    )
+}
+
+/// Moves `amount` tokens out of a token account owned by the `TokenHouse` PDA.
+fn transfer_from_token_house<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_house: &Account<'info, TokenHouse>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let mint_key = mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[b"token_house", mint_key.as_ref(), &[token_house.bump]]];
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: token_house.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        mint.decimals,
+    )
+}
+
+/// Records the outcome of a pending bet from its MPC result.
+///
+/// `wins` is the number of winning flips in the bet (0 or 1 unless it is a batch), or
+/// `None` if the computation was aborted. Returns what the player is owed: their share of
+/// the payout, or the stake back if the computation was aborted.
+fn record_outcome(flip_bet: &mut FlipBet, wins: Option<u8>) -> Result<u64> {
+    require!(
+        flip_bet.status == FlipStatus::Pending,
+        ErrorCode::BetAlreadySettled
+    );
+
+    let (status, owed) = match wins {
+        Some(wins) => {
+            require!(wins <= flip_bet.flip_count, ErrorCode::InvalidResult);
+
+            // Each winning flip pays its share of the full payout
+            let owed =
+                (flip_bet.payout as u128 * wins as u128 / flip_bet.flip_count as u128) as u64;
+            let status = if wins > 0 {
+                FlipStatus::Won
+            } else {
+                FlipStatus::Lost
+            };
+            (status, owed)
+        }
+        None => (FlipStatus::Aborted, flip_bet.wager),
+    };
+
+    flip_bet.status = status;
+    flip_bet.wins = wins.unwrap_or(0);
+    flip_bet.settled_at = Clock::get()?.unix_timestamp;
+
+    Ok(owed)
+}
+
+/// Settles a pending SOL bet from its MPC result and records the outcome.
+///
+/// `wins` is as for `record_outcome`. Returns the lamports sent to the player: their
+/// winnings, or the refunded stake if the computation was aborted.
+fn settle_bet<'info>(
+    flip_bet: &mut Account<'info, FlipBet>,
+    vault: &Account<'info, Vault>,
+    house: &mut Account<'info, House>,
+    player: &SystemAccount<'info>,
+    wins: Option<u8>,
+) -> Result<u64> {
+    let wager = flip_bet.wager;
+    let house_risk = flip_bet.payout.saturating_sub(wager);
+    let owed = record_outcome(flip_bet, wins)?;
+
+    // The reservation is released whatever the outcome
+    house.reserved = house.reserved.saturating_sub(house_risk);
+    vault.sub_lamports(wager)?;
+    if owed > wager {
+        house.sub_lamports(owed - wager)?;
+    } else {
+        house.add_lamports(wager - owed)?;
+    }
+    player.add_lamports(owed)?;
+
+    Ok(owed)
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct House {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Account allowed to update the edge and withdraw from the treasury
+    pub authority: Pubkey,
+    /// House edge in basis points, taken from the fair payout on a win
+    pub house_edge_bps: u16,
+    /// Lamports set aside to pay out flips that are still pending
+    pub reserved: u64,
+}
+
+impl House {
+    /// Amount paid to the player on a win: the fair payout for the odds minus the house edge.
+    ///
+    /// A coin flip (1 of 2 outcomes) pays 2x the wager before the edge is taken.
+    pub fn payout_for(
+        &self,
+        wager: u64,
+        winning_outcomes: u64,
+        total_outcomes: u64,
+    ) -> Result<u64> {
+        // Apply the odds and the edge in a single division so the payout is floored once
+        let net = wager as u128
+            * total_outcomes as u128
+            * (BASIS_POINTS - self.house_edge_bps as u64) as u128
+            / (winning_outcomes as u128 * BASIS_POINTS as u128);
+        u64::try_from(net).map_err(|_| ErrorCode::Overflow.into())
+    }
+}
+
+/// Treasury lamports not needed for rent exemption or pending payouts.
+fn available_house_lamports(house: &Account<House>) -> Result<u64> {
+    let rent = Rent::get()?.minimum_balance(8 + House::INIT_SPACE);
+    Ok(house
+        .get_lamports()
+        .saturating_sub(rent)
+        .saturating_sub(house.reserved))
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+/// House for flips wagered in one SPL token, using the SOL house's edge.
+#[account]
+#[derive(InitSpace)]
+pub struct TokenHouse {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Mint of the token wagered against this house
+    pub mint: Pubkey,
+    /// Token account holding stakes of flips still waiting on their MPC result
+    pub vault: Pubkey,
+    /// Token account paying out winnings and collecting lost stakes
+    pub treasury: Pubkey,
+    /// Tokens set aside to pay out flips that are still pending
+    pub reserved: u64,
+}
+
+impl TokenHouse {
+    /// Reserves `house_risk` tokens of the treasury, which holds `treasury_balance`.
+    fn reserve(&mut self, house_risk: u64, treasury_balance: u64) -> Result<()> {
+        require!(
+            house_risk <= treasury_balance.saturating_sub(self.reserved),
+            ErrorCode::InsufficientHouseFunds
+        );
+        self.reserved = self
+            .reserved
+            .checked_add(house_risk)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PrivateFlip {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Player who queued the flip and receives the rent back once it is delivered
+    pub player: Pubkey,
+    /// Offset of the MPC computation that delivers the outcome, part of the PDA seeds
+    pub computation_offset: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum FlipStatus {
+    Pending,
+    Won,
+    Lost,
+    Aborted,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct FlipBet {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Player who placed the wager and receives any payout or refund
+    pub player: Pubkey,
+    /// Player-chosen identifier, part of the PDA seeds
+    pub bet_nonce: u64,
+    /// Offset of the MPC computation that settles this bet
+    pub computation_offset: u64,
+    /// Mint of the wagered token, `None` for a SOL wager
+    pub mint: Option<Pubkey>,
+    /// Stake in lamports (or token base units), held in the vault until the flip is settled
+    pub wager: u64,
+    /// Amount owed to the player if every flip in the bet is won
+    pub payout: u64,
+    /// Number of flips covered by the wager (1 unless placed with `flip_batch`)
+    pub flip_count: u8,
+    /// Number of flips won, set when the bet is settled
+    pub wins: u8,
+    /// Current state of the bet (pending until the callback runs; won if any flip won)
+    pub status: FlipStatus,
+    /// Unix timestamp at which the flip was queued
+    pub created_at: i64,
+    /// Unix timestamp at which the callback settled the bet (0 while pending)
+    pub settled_at: i64,
+}
+
+impl FlipBet {
+    /// Records a newly queued bet as pending.
+    #[allow(clippy::too_many_arguments)]
+    pub fn open(
+        &mut self,
+        bump: u8,
+        player: Pubkey,
+        bet_nonce: u64,
+        computation_offset: u64,
+        flip_count: u8,
+        mint: Option<Pubkey>,
+        wager: u64,
+        payout: u64,
+    ) -> Result<()> {
+        self.bump = bump;
+        self.player = player;
+        self.bet_nonce = bet_nonce;
+        self.computation_offset = computation_offset;
+        self.flip_count = flip_count;
+        self.mint = mint;
+        self.wins = 0;
+        self.wager = wager;
+        self.payout = payout;
+        self.status = FlipStatus::Pending;
+        self.created_at = Clock::get()?.unix_timestamp;
+        self.settled_at = 0;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeHouse<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + House::INIT_SPACE,
+        seeds = [b"house"],
+        bump,
+    )]
+    pub house: Account<'info, House>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault"],
+        bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateHouse<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"house"],
+        bump = house.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub house: Account<'info, House>,
+}
+
+#[derive(Accounts)]
+pub struct FundHouse<'info> {
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"house"],
+        bump = house.bump,
+    )]
+    pub house: Account<'info, House>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTokenHouse<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"house"],
+        bump = house.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub house: Account<'info, House>,
+    #[account(mint::token_program = token_program)]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TokenHouse::INIT_SPACE,
+        seeds = [b"token_house", mint.key().as_ref()],
+        bump,
+    )]
+    pub token_house: Account<'info, TokenHouse>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"token_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = token_house,
+        token::token_program = token_program,
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"token_treasury", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = token_house,
+        token::token_program = token_program,
+    )]
+    pub token_treasury: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTokenHouse<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"house"],
+        bump = house.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub house: Account<'info, House>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        seeds = [b"token_house", mint.key().as_ref()],
+        bump = token_house.bump,
+        has_one = mint,
+    )]
+    pub token_house: Account<'info, TokenHouse>,
+    #[account(
+        mut,
+        address = token_house.treasury @ ErrorCode::InvalidTokenAccounts,
+    )]
+    pub token_treasury: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub destination: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[queue_computation_accounts("flip", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, bet_nonce: u64)]
+pub struct Flip<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"house"],
+        bump = house.bump,
+    )]
+    pub house: Box<Account<'info, House>>,
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = vault.bump,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + FlipBet::INIT_SPACE,
+        seeds = [b"flip_bet", payer.key().as_ref(), bet_nonce.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub flip_bet: Box<Account<'info, FlipBet>>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_FLIP)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        mut,
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    /// Mint of the wagered token, left out for a SOL wager
+    pub mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+    #[account(
+        mut,
+        seeds = [b"token_house", token_house.mint.as_ref()],
+        bump = token_house.bump,
+    )]
+    pub token_house: Option<Box<Account<'info, TokenHouse>>>,
+    #[account(mut)]
+    pub token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    pub token_treasury: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+impl<'info> Flip<'info> {
+    /// Prices a token wager against its `TokenHouse`, reserves the house's share of the
+    /// payout and moves the stake into the token vault.
+    ///
+    /// Returns the payout owed if the flip is won.
+    fn escrow_token_wager(&mut self, wager: u64) -> Result<u64> {
+        let (
+            Some(mint),
+            Some(token_house),
+            Some(token_vault),
+            Some(token_treasury),
+            Some(player_token_account),
+            Some(token_program),
+        ) = (
+            self.mint.as_ref(),
+            self.token_house.as_mut(),
+            self.token_vault.as_ref(),
+            self.token_treasury.as_ref(),
+            self.player_token_account.as_ref(),
+            self.token_program.as_ref(),
+        )
+        else {
+            return err!(ErrorCode::MissingTokenAccounts);
+        };
+        require_keys_eq!(
+            token_house.mint,
+            mint.key(),
+            ErrorCode::InvalidTokenAccounts
+        );
+        require_keys_eq!(
+            token_house.vault,
+            token_vault.key(),
+            ErrorCode::InvalidTokenAccounts
+        );
+        require_keys_eq!(
+            token_house.treasury,
+            token_treasury.key(),
+            ErrorCode::InvalidTokenAccounts
+        );
+
+        // A coin flip wins on one of two equally likely outcomes
+        let (payout, house_risk) = price_bet(&self.house, wager, 1, 2)?;
+        token_house.reserve(house_risk, token_treasury.amount)?;
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                token_program.to_account_info(),
+                TransferChecked {
+                    from: player_token_account.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: token_vault.to_account_info(),
+                    authority: self.payer.to_account_info(),
+                },
+            ),
+            wager,
+            mint.decimals,
+        )?;
+        Ok(payout)
+    }
+}
+
+#[callback_accounts("flip")]
+#[derive(Accounts)]
+pub struct FlipCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_FLIP)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"flip_bet", player.key().as_ref(), flip_bet.bet_nonce.to_le_bytes().as_ref()],
+        bump = flip_bet.bump,
+        has_one = player,
+    )]
+    pub flip_bet: Account<'info, FlipBet>,
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"house"],
+        bump = house.bump,
+    )]
+    pub house: Account<'info, House>,
+    #[account(mut)]
+    pub player: SystemAccount<'info>,
+    pub mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+    #[account(
+        mut,
+        seeds = [b"token_house", token_house.mint.as_ref()],
+        bump = token_house.bump,
+    )]
+    pub token_house: Option<Box<Account<'info, TokenHouse>>>,
+    #[account(mut)]
+    pub token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub token_treasury: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+impl<'info> FlipCallback<'info> {
+    /// Settles a pending token bet from its MPC result, as `settle_bet` does for SOL.
+    ///
+    /// The stake leaves the token vault for the player and the treasury covers or collects
+    /// the difference. Returns the tokens sent to the player.
+    fn settle_token_bet(&mut self, wins: Option<u8>) -> Result<u64> {
+        let (
+            Some(mint),
+            Some(token_house),
+            Some(token_vault),
+            Some(token_treasury),
+            Some(player_token_account),
+            Some(token_program),
+        ) = (
+            self.mint.as_ref(),
+            self.token_house.as_mut(),
+            self.token_vault.as_ref(),
+            self.token_treasury.as_ref(),
+            self.player_token_account.as_ref(),
+            self.token_program.as_ref(),
+        )
+        else {
+            return err!(ErrorCode::MissingTokenAccounts);
+        };
+        require!(
+            self.flip_bet.mint == Some(mint.key()) && token_house.mint == mint.key(),
+            ErrorCode::InvalidTokenAccounts
+        );
+        require_keys_eq!(
+            token_house.vault,
+            token_vault.key(),
+            ErrorCode::InvalidTokenAccounts
+        );
+        require_keys_eq!(
+            token_house.treasury,
+            token_treasury.key(),
+            ErrorCode::InvalidTokenAccounts
+        );
+
+        let wager = self.flip_bet.wager;
+        let house_risk = self.flip_bet.payout.saturating_sub(wager);
+        let owed = record_outcome(&mut self.flip_bet, wins)?;
+
+        // The reservation is released whatever the outcome
+        token_house.reserved = token_house.reserved.saturating_sub(house_risk);
+
+        transfer_from_token_house(
+            token_program,
+            mint,
+            token_house,
+            token_vault,
+            player_token_account,
+            wager.min(owed),
+        )?;
+        if owed > wager {
+            transfer_from_token_house(
+                token_program,
+                mint,
+                token_house,
+                token_treasury,
+                player_token_account,
+                owed - wager,
+            )?;
+        } else {
+            transfer_from_token_house(
+                token_program,
+                mint,
+                token_house,
+                token_vault,
+                token_treasury,
+                wager - owed,
+            )?;
+        }
+
+        Ok(owed)
+    }
+}
+
+#[init_computation_definition_accounts("flip", payer)]
+#[derive(Accounts)]
+pub struct InitFlipCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table, checked by arcium program.
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program is the Address Lookup Table program.
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("flip_private", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct FlipPrivate<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PrivateFlip::INIT_SPACE,
+        seeds = [b"private_flip", computation_offset.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub private_flip: Box<Account<'info, PrivateFlip>>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_FLIP_PRIVATE)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        mut,
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("flip_private")]
+#[derive(Accounts)]
+pub struct FlipPrivateCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_FLIP_PRIVATE)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"private_flip", private_flip.computation_offset.to_le_bytes().as_ref()],
+        bump = private_flip.bump,
+        has_one = player,
+        close = player,
+    )]
+    pub private_flip: Account<'info, PrivateFlip>,
+    #[account(mut)]
+    pub player: SystemAccount<'info>,
+}
 
-        Ok(())
-    }
+#[init_computation_definition_accounts("flip_private", payer)]
+#[derive(Accounts)]
+pub struct InitFlipPrivateCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table, checked by arcium program.
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program is the Address Lookup Table program.
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
 
-    /// Handles the result of the coin flip MPC computation.
-    ///
-    /// This callback receives the result of comparing the player's choice with the
-    /// randomly generated coin flip. The result is a boolean indicating whether
-    /// the player won (true) or lost (false).
-    #[arcium_callback(encrypted_ix = "flip")]
-    pub fn flip_callback(
-        ctx: Context<FlipCallback>,
-        output: SignedComputationOutputs<FlipOutput>,
-    ) -> Result<()> {
-        let o = match output.verify_output(
-            &ctx.accounts.cluster_account,
-            &ctx.accounts.computation_account,
-        ) {
-            Ok(FlipOutput { field_0 }) => field_0,
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
-        };
+#[queue_computation_accounts("flip_batch", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, bet_nonce: u64)]
+pub struct FlipBatch<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"house"],
+        bump = house.bump,
+    )]
+    pub house: Box<Account<'info, House>>,
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = vault.bump,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + FlipBet::INIT_SPACE,
+        seeds = [b"flip_bet", payer.key().as_ref(), bet_nonce.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub flip_bet: Box<Account<'info, FlipBet>>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_FLIP_BATCH)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        mut,
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
 
-        emit!(FlipEvent { result: o });
+#[callback_accounts("flip_batch")]
+#[derive(Accounts)]
+pub struct FlipBatchCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_FLIP_BATCH)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"flip_bet", player.key().as_ref(), flip_bet.bet_nonce.to_le_bytes().as_ref()],
+        bump = flip_bet.bump,
+        has_one = player,
+    )]
+    pub flip_bet: Account<'info, FlipBet>,
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"house"],
+        bump = house.bump,
+    )]
+    pub house: Account<'info, House>,
+    #[account(mut)]
+    pub player: SystemAccount<'info>,
+}
 
-        Ok(())
-    }
+#[init_computation_definition_accounts("flip_batch", payer)]
+#[derive(Accounts)]
+pub struct InitFlipBatchCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table, checked by arcium program.
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program is the Address Lookup Table program.
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
 }
 
-#[queue_computation_accounts("flip", payer)]
+#[queue_computation_accounts("roll_dice", payer)]
 #[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct Flip<'info> {
+#[instruction(computation_offset: u64, bet_nonce: u64)]
+pub struct RollDice<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"house"],
+        bump = house.bump,
+    )]
+    pub house: Box<Account<'info, House>>,
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = vault.bump,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + FlipBet::INIT_SPACE,
+        seeds = [b"flip_bet", payer.key().as_ref(), bet_nonce.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub flip_bet: Box<Account<'info, FlipBet>>,
     #[account(
         init_if_needed,
         space = 9,
@@ -119,7 +1768,7 @@ pub struct Flip<'info> {
     /// CHECK: computation_account, checked by the arcium program.
     pub computation_account: UncheckedAccount<'info>,
     #[account(
-        address = derive_comp_def_pda!(COMP_DEF_OFFSET_FLIP)
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_ROLL_DICE)
     )]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(
@@ -141,12 +1790,12 @@ pub struct Flip<'info> {
     pub arcium_program: Program<'info, Arcium>,
 }
 
-#[callback_accounts("flip")]
+#[callback_accounts("roll_dice")]
 #[derive(Accounts)]
-pub struct FlipCallback<'info> {
+pub struct RollDiceCallback<'info> {
     pub arcium_program: Program<'info, Arcium>,
     #[account(
-        address = derive_comp_def_pda!(COMP_DEF_OFFSET_FLIP)
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_ROLL_DICE)
     )]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(
@@ -162,11 +1811,32 @@ pub struct FlipCallback<'info> {
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar, checked by the account constraint
     pub instructions_sysvar: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"flip_bet", player.key().as_ref(), flip_bet.bet_nonce.to_le_bytes().as_ref()],
+        bump = flip_bet.bump,
+        has_one = player,
+    )]
+    pub flip_bet: Account<'info, FlipBet>,
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"house"],
+        bump = house.bump,
+    )]
+    pub house: Account<'info, House>,
+    #[account(mut)]
+    pub player: SystemAccount<'info>,
 }
 
-#[init_computation_definition_accounts("flip", payer)]
+#[init_computation_definition_accounts("roll_dice", payer)]
 #[derive(Accounts)]
-pub struct InitFlipCompDef<'info> {
+pub struct InitRollDiceCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(
@@ -191,8 +1861,67 @@ pub struct InitFlipCompDef<'info> {
 /// Event emitted when a coin flip game completes.
 #[event]
 pub struct FlipEvent {
+    /// Bet account holding the settled outcome
+    pub flip_bet: Pubkey,
     /// Whether the player won the coin flip (true = won, false = lost)
     pub result: bool,
+    /// Player who placed the wager
+    pub player: Pubkey,
+    /// Stake in lamports, or token base units for a token wager
+    pub wager: u64,
+    /// Amount paid to the player (0 on a loss)
+    pub payout: u64,
+}
+
+/// Event emitted when a private coin flip completes.
+#[event]
+pub struct FlipPrivateEvent {
+    /// Player who requested the flip and holds the key to decrypt the outcome
+    pub player: Pubkey,
+    /// Offset of the MPC computation that produced the outcome
+    pub computation_offset: u64,
+    /// Outcome encrypted to the player (true = won, false = lost)
+    pub result: [u8; 32],
+    /// Nonce used for encrypting the outcome
+    pub nonce: [u8; 16],
+}
+
+/// Event emitted when a batch of coin flips completes.
+#[event]
+pub struct FlipBatchEvent {
+    /// Bet account holding the settled outcome
+    pub flip_bet: Pubkey,
+    /// Outcome of each flip, in submission order (true = won, false = lost)
+    pub results: Vec<bool>,
+    /// Player who placed the wager
+    pub player: Pubkey,
+    /// Total stake in lamports across all flips
+    pub wager: u64,
+    /// Lamports paid to the player for the winning flips
+    pub payout: u64,
+}
+
+/// Event emitted when a dice roll completes.
+#[event]
+pub struct DiceRollEvent {
+    /// Bet account holding the settled outcome
+    pub flip_bet: Pubkey,
+    /// Whether the roll landed in the player's window (true = won, false = lost)
+    pub result: bool,
+    /// Player who placed the wager
+    pub player: Pubkey,
+    /// Stake in lamports
+    pub wager: u64,
+    /// Lamports paid to the player (0 on a loss)
+    pub payout: u64,
+}
+
+/// Event emitted when an aborted flip, batch or dice roll has its stake returned to the player.
+#[event]
+pub struct FlipRefundedEvent {
+    pub flip_bet: Pubkey,
+    pub player: Pubkey,
+    pub wager: u64,
 }
 
 #[error_code]
@@ -201,4 +1930,24 @@ pub enum ErrorCode {
     AbortedComputation,
     #[msg("The cluster is not set")]
     ClusterNotSet,
+    #[msg("House edge exceeds the allowed maximum")]
+    InvalidHouseEdge,
+    #[msg("Wager must be greater than zero")]
+    InvalidWager,
+    #[msg("House treasury cannot cover this payout")]
+    InsufficientHouseFunds,
+    #[msg("Not authorized to perform this action")]
+    Unauthorized,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Bet has already been settled")]
+    BetAlreadySettled,
+    #[msg("Win count must be between 1 and 99 and pay more than the wager")]
+    InvalidOdds,
+    #[msg("Unexpected result value from computation")]
+    InvalidResult,
+    #[msg("A token wager needs the mint, token house and token accounts")]
+    MissingTokenAccounts,
+    #[msg("Token accounts do not belong to this token house")]
+    InvalidTokenAccounts,
 }
@@ -1,6 +1,13 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
-import { PublicKey } from "@solana/web3.js";
+import { LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import {
+  createAssociatedTokenAccount,
+  createMint,
+  getAccount,
+  mintTo,
+  TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
 import { Coinflip } from "../target/types/coinflip";
 import { randomBytes } from "crypto";
 import {
@@ -25,6 +32,7 @@ import {
 } from "@arcium-hq/client";
 import * as fs from "fs";
 import * as os from "os";
+import { expect } from "chai";
 
 describe("Coinflip", () => {
   // Configure the client to use the local cluster.
@@ -73,6 +81,25 @@ describe("Coinflip", () => {
       initFlipSig
     );
 
+    const [housePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("house")],
+      program.programId
+    );
+
+    // 2% house edge
+    await program.methods
+      .initializeHouse(200)
+      .accounts({ authority: owner.publicKey })
+      .signers([owner])
+      .rpc({ commitment: "confirmed" });
+
+    await program.methods
+      .fundHouse(new anchor.BN(LAMPORTS_PER_SOL))
+      .accounts({ funder: owner.publicKey })
+      .signers([owner])
+      .rpc({ commitment: "confirmed" });
+    console.log("House treasury funded at", housePDA.toBase58());
+
     const privateKey = x25519.utils.randomSecretKey();
     const publicKey = x25519.getPublicKey(privateKey);
     const sharedSecret = x25519.getSharedSecret(privateKey, mxePublicKey);
@@ -87,13 +114,25 @@ describe("Coinflip", () => {
     const flipEventPromise = awaitEvent("flipEvent");
 
     const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    const wager = new anchor.BN(LAMPORTS_PER_SOL / 10);
+    const betNonce = new anchor.BN(randomBytes(8), "hex");
+    const [flipBetPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("flip_bet"),
+        owner.publicKey.toBuffer(),
+        betNonce.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
 
     const queueSig = await program.methods
       .flip(
         computationOffset,
+        betNonce,
         Array.from(ciphertext[0]),
         Array.from(publicKey),
-        new anchor.BN(deserializeLE(nonce).toString())
+        new anchor.BN(deserializeLE(nonce).toString()),
+        wager
       )
       .accountsPartial({
         computationAccount: getComputationAccAddress(
@@ -127,13 +166,445 @@ describe("Coinflip", () => {
 
     const flipEvent = await flipEventPromise;
 
+    expect(flipEvent.wager.toString()).to.equal(wager.toString());
+    expect(flipEvent.flipBet.toBase58()).to.equal(flipBetPDA.toBase58());
+
+    // The outcome can also be reconciled from chain state alone
+    const flipBet = await program.account.flipBet.fetch(flipBetPDA);
+    expect(flipBet.computationOffset.toString()).to.equal(
+      computationOffset.toString()
+    );
+    expect(flipBet.status).to.deep.equal(
+      flipEvent.result ? { won: {} } : { lost: {} }
+    );
+    expect(flipBet.settledAt.toNumber()).to.be.greaterThan(0);
     if (flipEvent.result) {
-      console.log("Congratulations! You won!");
+      console.log(
+        "Congratulations! You won",
+        flipEvent.payout.toString(),
+        "lamports!"
+      );
+      // 2x minus the 2% edge
+      expect(flipEvent.payout.toString()).to.equal(
+        wager.muln(2).muln(9800).divn(10000).toString()
+      );
     } else {
       console.log("Better luck next time!");
     }
   });
 
+  it("flip a coin for tokens!", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+
+    const mint = await createMint(
+      provider.connection,
+      owner,
+      owner.publicKey,
+      null,
+      0
+    );
+    const [tokenHousePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("token_house"), mint.toBuffer()],
+      program.programId
+    );
+    const [tokenVaultPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("token_vault"), mint.toBuffer()],
+      program.programId
+    );
+    const [tokenTreasuryPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("token_treasury"), mint.toBuffer()],
+      program.programId
+    );
+
+    // Uses the house edge set by the SOL house in the first test
+    await program.methods
+      .initializeTokenHouse()
+      .accounts({
+        authority: owner.publicKey,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([owner])
+      .rpc({ commitment: "confirmed" });
+
+    // The treasury is funded with a plain token transfer
+    await mintTo(
+      provider.connection,
+      owner,
+      mint,
+      tokenTreasuryPDA,
+      owner,
+      1_000_000
+    );
+    const playerTokenAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      owner,
+      mint,
+      owner.publicKey
+    );
+    const startingBalance = 100_000;
+    await mintTo(
+      provider.connection,
+      owner,
+      mint,
+      playerTokenAccount,
+      owner,
+      startingBalance
+    );
+
+    const privateKey = x25519.utils.randomSecretKey();
+    const publicKey = x25519.getPublicKey(privateKey);
+    const sharedSecret = x25519.getSharedSecret(privateKey, mxePublicKey);
+    const cipher = new RescueCipher(sharedSecret);
+
+    const nonce = randomBytes(16);
+    const ciphertext = cipher.encrypt([BigInt(false)], nonce);
+
+    const flipEventPromise = awaitEvent("flipEvent");
+
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    const wager = new anchor.BN(10_000);
+    const betNonce = new anchor.BN(randomBytes(8), "hex");
+    const [flipBetPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("flip_bet"),
+        owner.publicKey.toBuffer(),
+        betNonce.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .flip(
+        computationOffset,
+        betNonce,
+        Array.from(ciphertext[0]),
+        Array.from(publicKey),
+        new anchor.BN(deserializeLE(nonce).toString()),
+        wager
+      )
+      .accountsPartial({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("flip")).readUInt32LE()
+        ),
+        mint,
+        tokenHouse: tokenHousePDA,
+        tokenVault: tokenVaultPDA,
+        tokenTreasury: tokenTreasuryPDA,
+        playerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({
+        skipPreflight: true,
+        commitment: "confirmed",
+      });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+    const flipEvent = await flipEventPromise;
+    expect(flipEvent.flipBet.toBase58()).to.equal(flipBetPDA.toBase58());
+
+    const flipBet = await program.account.flipBet.fetch(flipBetPDA);
+    expect(flipBet.mint.toBase58()).to.equal(mint.toBase58());
+    expect(flipBet.status).to.deep.equal(
+      flipEvent.result ? { won: {} } : { lost: {} }
+    );
+
+    // The stake left the vault and the reservation was released
+    const player = await getAccount(provider.connection, playerTokenAccount);
+    const vault = await getAccount(provider.connection, tokenVaultPDA);
+    const tokenHouse = await program.account.tokenHouse.fetch(tokenHousePDA);
+    expect(Number(player.amount)).to.equal(
+      startingBalance - wager.toNumber() + flipEvent.payout.toNumber()
+    );
+    expect(Number(vault.amount)).to.equal(0);
+    expect(tokenHouse.reserved.toNumber()).to.equal(0);
+    if (flipEvent.result) {
+      // 2x minus the 2% edge
+      expect(flipEvent.payout.toNumber()).to.equal(19_600);
+    }
+  });
+
+  it("roll a die!", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+
+    console.log("Initializing roll dice computation definition");
+    const initRollDiceSig = await initRollDiceCompDef(program, owner);
+    console.log(
+      "Roll dice computation definition initialized with signature",
+      initRollDiceSig
+    );
+
+    const privateKey = x25519.utils.randomSecretKey();
+    const publicKey = x25519.getPublicKey(privateKey);
+    const sharedSecret = x25519.getSharedSecret(privateKey, mxePublicKey);
+    const cipher = new RescueCipher(sharedSecret);
+
+    // Roll under 25: faces 0..24 win
+    const target = BigInt(0);
+    const winCount = 25;
+
+    const nonce = randomBytes(16);
+    const ciphertext = cipher.encrypt([target], nonce);
+
+    const diceRollEventPromise = awaitEvent("diceRollEvent");
+
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    const wager = new anchor.BN(LAMPORTS_PER_SOL / 100);
+    const betNonce = new anchor.BN(randomBytes(8), "hex");
+
+    const queueSig = await program.methods
+      .rollDice(
+        computationOffset,
+        betNonce,
+        Array.from(ciphertext[0]),
+        Array.from(publicKey),
+        new anchor.BN(deserializeLE(nonce).toString()),
+        winCount,
+        wager
+      )
+      .accountsPartial({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("roll_dice")).readUInt32LE()
+        ),
+      })
+      .rpc({
+        skipPreflight: true,
+        commitment: "confirmed",
+      });
+    console.log("Queue sig is ", queueSig);
+
+    const finalizeSig = await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+    console.log("Finalize sig is ", finalizeSig);
+
+    const diceRollEvent = await diceRollEventPromise;
+
+    if (diceRollEvent.result) {
+      // 100/25 = 4x minus the 2% edge
+      expect(diceRollEvent.payout.toString()).to.equal(
+        wager.muln(4).muln(9800).divn(10000).toString()
+      );
+      console.log("Rolled under 25!");
+    } else {
+      expect(diceRollEvent.payout.toNumber()).to.equal(0);
+      console.log("Rolled 25 or higher");
+    }
+  });
+
+  it("flip a batch of coins!", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+
+    console.log("Initializing flip batch computation definition");
+    const initFlipBatchSig = await initFlipBatchCompDef(program, owner);
+    console.log(
+      "Flip batch computation definition initialized with signature",
+      initFlipBatchSig
+    );
+
+    const privateKey = x25519.utils.randomSecretKey();
+    const publicKey = x25519.getPublicKey(privateKey);
+    const sharedSecret = x25519.getSharedSecret(privateKey, mxePublicKey);
+    const cipher = new RescueCipher(sharedSecret);
+
+    const batchSize = 8;
+    const choices = Array.from({ length: batchSize }, (_, i) =>
+      BigInt(i % 2 === 0)
+    );
+
+    const nonce = randomBytes(16);
+    const ciphertext = cipher.encrypt(choices, nonce);
+
+    const flipBatchEventPromise = awaitEvent("flipBatchEvent");
+
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    const wagerPerFlip = new anchor.BN(LAMPORTS_PER_SOL / 100);
+    const betNonce = new anchor.BN(randomBytes(8), "hex");
+
+    const queueSig = await program.methods
+      .flipBatch(
+        computationOffset,
+        betNonce,
+        ciphertext.map((c) => Array.from(c)),
+        Array.from(publicKey),
+        new anchor.BN(deserializeLE(nonce).toString()),
+        wagerPerFlip
+      )
+      .accountsPartial({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("flip_batch")).readUInt32LE()
+        ),
+      })
+      .rpc({
+        skipPreflight: true,
+        commitment: "confirmed",
+      });
+    console.log("Queue sig is ", queueSig);
+
+    const finalizeSig = await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+    console.log("Finalize sig is ", finalizeSig);
+
+    const flipBatchEvent = await flipBatchEventPromise;
+
+    expect(flipBatchEvent.results).to.have.lengthOf(batchSize);
+    const wins = flipBatchEvent.results.filter((won) => won).length;
+    console.log(`Won ${wins} of ${batchSize} flips`);
+
+    // Each winning flip pays its share of 2x the total stake minus the 2% edge
+    const fullPayout = wagerPerFlip
+      .muln(batchSize)
+      .muln(2)
+      .muln(9800)
+      .divn(10000);
+    expect(flipBatchEvent.payout.toString()).to.equal(
+      fullPayout.muln(wins).divn(batchSize).toString()
+    );
+  });
+
+  it("flip a coin privately!", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+
+    console.log("Initializing flip private computation definition");
+    const initFlipPrivateSig = await initFlipPrivateCompDef(program, owner);
+    console.log(
+      "Flip private computation definition initialized with signature",
+      initFlipPrivateSig
+    );
+
+    const privateKey = x25519.utils.randomSecretKey();
+    const publicKey = x25519.getPublicKey(privateKey);
+    const sharedSecret = x25519.getSharedSecret(privateKey, mxePublicKey);
+    const cipher = new RescueCipher(sharedSecret);
+
+    const nonce = randomBytes(16);
+    const ciphertext = cipher.encrypt([BigInt(true)], nonce);
+    const resultNonce = randomBytes(16);
+
+    const flipPrivateEventPromise = awaitEvent("flipPrivateEvent");
+
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+
+    const queueSig = await program.methods
+      .flipPrivate(
+        computationOffset,
+        Array.from(ciphertext[0]),
+        Array.from(publicKey),
+        new anchor.BN(deserializeLE(nonce).toString()),
+        new anchor.BN(deserializeLE(resultNonce).toString())
+      )
+      .accountsPartial({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("flip_private")).readUInt32LE()
+        ),
+      })
+      .rpc({
+        skipPreflight: true,
+        commitment: "confirmed",
+      });
+    console.log("Queue sig is ", queueSig);
+
+    const finalizeSig = await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+    console.log("Finalize sig is ", finalizeSig);
+
+    const flipPrivateEvent = await flipPrivateEventPromise;
+    expect(flipPrivateEvent.player.toBase58()).to.equal(
+      provider.wallet.publicKey.toBase58()
+    );
+    expect(flipPrivateEvent.computationOffset.eq(computationOffset)).to.equal(
+      true
+    );
+
+    // Only the player's key can decrypt the outcome
+    const won = cipher.decrypt(
+      [flipPrivateEvent.result],
+      new Uint8Array(flipPrivateEvent.nonce)
+    )[0];
+    expect([BigInt(0), BigInt(1)]).to.include(won);
+    console.log(won === BigInt(1) ? "You won!" : "Better luck next time!");
+  });
+
   async function initFlipCompDef(
     program: Program<Coinflip>,
     owner: anchor.web3.Keypair
@@ -184,6 +655,159 @@ describe("Coinflip", () => {
 
     return sig;
   }
+
+  async function initFlipPrivateCompDef(
+    program: Program<Coinflip>,
+    owner: anchor.web3.Keypair
+  ): Promise<string> {
+    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+      "ComputationDefinitionAccount"
+    );
+    const offset = getCompDefAccOffset("flip_private");
+
+    const compDefPDA = PublicKey.findProgramAddressSync(
+      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+      getArciumProgramId()
+    )[0];
+
+    console.log("Comp def pda is ", compDefPDA);
+
+    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
+    const mxeAccount = getMXEAccAddress(program.programId);
+    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+    const lutAddress = getLookupTableAddress(
+      program.programId,
+      mxeAcc.lutOffsetSlot
+    );
+
+    const sig = await program.methods
+      .initFlipPrivateCompDef()
+      .accounts({
+        compDefAccount: compDefPDA,
+        payer: owner.publicKey,
+        mxeAccount,
+        addressLookupTable: lutAddress,
+      })
+      .signers([owner])
+      .rpc({
+        preflightCommitment: "confirmed",
+        commitment: "confirmed",
+      });
+    console.log("Init flip private computation definition transaction", sig);
+
+    const rawCircuit = fs.readFileSync("build/flip_private.arcis");
+    await uploadCircuit(
+      provider as anchor.AnchorProvider,
+      "flip_private",
+      program.programId,
+      rawCircuit,
+      true
+    );
+
+    return sig;
+  }
+
+  async function initFlipBatchCompDef(
+    program: Program<Coinflip>,
+    owner: anchor.web3.Keypair
+  ): Promise<string> {
+    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+      "ComputationDefinitionAccount"
+    );
+    const offset = getCompDefAccOffset("flip_batch");
+
+    const compDefPDA = PublicKey.findProgramAddressSync(
+      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+      getArciumProgramId()
+    )[0];
+
+    console.log("Comp def pda is ", compDefPDA);
+
+    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
+    const mxeAccount = getMXEAccAddress(program.programId);
+    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+    const lutAddress = getLookupTableAddress(
+      program.programId,
+      mxeAcc.lutOffsetSlot
+    );
+
+    const sig = await program.methods
+      .initFlipBatchCompDef()
+      .accounts({
+        compDefAccount: compDefPDA,
+        payer: owner.publicKey,
+        mxeAccount,
+        addressLookupTable: lutAddress,
+      })
+      .signers([owner])
+      .rpc({
+        preflightCommitment: "confirmed",
+        commitment: "confirmed",
+      });
+    console.log("Init flip batch computation definition transaction", sig);
+
+    const rawCircuit = fs.readFileSync("build/flip_batch.arcis");
+    await uploadCircuit(
+      provider as anchor.AnchorProvider,
+      "flip_batch",
+      program.programId,
+      rawCircuit,
+      true
+    );
+
+    return sig;
+  }
+
+  async function initRollDiceCompDef(
+    program: Program<Coinflip>,
+    owner: anchor.web3.Keypair
+  ): Promise<string> {
+    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+      "ComputationDefinitionAccount"
+    );
+    const offset = getCompDefAccOffset("roll_dice");
+
+    const compDefPDA = PublicKey.findProgramAddressSync(
+      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+      getArciumProgramId()
+    )[0];
+
+    console.log("Comp def pda is ", compDefPDA);
+
+    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
+    const mxeAccount = getMXEAccAddress(program.programId);
+    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+    const lutAddress = getLookupTableAddress(
+      program.programId,
+      mxeAcc.lutOffsetSlot
+    );
+
+    const sig = await program.methods
+      .initRollDiceCompDef()
+      .accounts({
+        compDefAccount: compDefPDA,
+        payer: owner.publicKey,
+        mxeAccount,
+        addressLookupTable: lutAddress,
+      })
+      .signers([owner])
+      .rpc({
+        preflightCommitment: "confirmed",
+        commitment: "confirmed",
+      });
+    console.log("Init roll dice computation definition transaction", sig);
+
+    const rawCircuit = fs.readFileSync("build/roll_dice.arcis");
+    await uploadCircuit(
+      provider as anchor.AnchorProvider,
+      "roll_dice",
+      program.programId,
+      rawCircuit,
+      true
+    );
+
+    return sig;
+  }
 });
 
 async function getMXEPublicKeyWithRetry(
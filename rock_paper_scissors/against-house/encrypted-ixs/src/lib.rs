@@ -9,8 +9,9 @@ mod circuits {
         player_move: u8,
     }
 
+    // Returns the result code and the house move, both revealed so the outcome can be audited
     #[instruction]
-    pub fn play_rps(player_move_ctxt: Enc<Shared, PlayerMove>) -> u8 {
+    pub fn play_rps(player_move_ctxt: Enc<Shared, PlayerMove>) -> (u8, u8) {
         let player_move = player_move_ctxt.to_arcis();
 
         let mut house_move: u8 = 0;
@@ -54,6 +55,59 @@ mod circuits {
             2
         };
 
-        result.reveal()
+        (result.reveal(), house_move.reveal())
+    }
+
+    // Rock-Paper-Scissors-Lizard-Spock: 0 - Rock, 1 - Paper, 2 - Scissors, 3 - Lizard, 4 - Spock
+    // Returns the same result codes as play_rps, together with the revealed house move
+    #[instruction]
+    pub fn play_rpsls(player_move_ctxt: Enc<Shared, PlayerMove>) -> (u8, u8) {
+        let player_move = player_move_ctxt.to_arcis();
+
+        let mut house_move: u8 = 0;
+        let mut selected = false;
+
+        // Draw 3 random bits per candidate and keep the first one below 5
+        for _ in 0..16 {
+            let b0 = ArcisRNG::bool();
+            let b1 = ArcisRNG::bool();
+            let b2 = ArcisRNG::bool();
+
+            let candidate: u8 =
+                (if b0 { 4 } else { 0 }) + (if b1 { 2 } else { 0 }) + (if b2 { 1 } else { 0 });
+
+            let candidate_valid = candidate < 5;
+            let take = (!selected) & candidate_valid;
+
+            house_move = if take { candidate } else { house_move };
+            selected = selected | candidate_valid;
+        }
+
+        let p = player_move.player_move;
+        let h = house_move;
+
+        // 0 - tie, 1 - player wins, 2 - house wins, 3 - invalid move
+        let result = if p > 4 {
+            3
+        } else if p == h {
+            0
+        } else if (p == 2 && h == 1) || // Scissors cuts Paper
+                  (p == 1 && h == 0) || // Paper covers Rock
+                  (p == 0 && h == 3) || // Rock crushes Lizard
+                  (p == 3 && h == 4) || // Lizard poisons Spock
+                  (p == 4 && h == 2) || // Spock smashes Scissors
+                  (p == 2 && h == 3) || // Scissors decapitates Lizard
+                  (p == 3 && h == 1) || // Lizard eats Paper
+                  (p == 1 && h == 4) || // Paper disproves Spock
+                  (p == 4 && h == 0) || // Spock vaporizes Rock
+                  (p == 0 && h == 2)
+        // Rock crushes Scissors
+        {
+            1
+        } else {
+            2
+        };
+
+        (result.reveal(), house_move.reveal())
     }
 }
@@ -1,7 +1,15 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
 use arcium_anchor::prelude::*;
+use arcium_client::idl::arcium::types::CallbackAccount;
 
 const COMP_DEF_OFFSET_PLAY_RPS: u32 = comp_def_offset("play_rps");
+const COMP_DEF_OFFSET_PLAY_RPSLS: u32 = comp_def_offset("play_rpsls");
+
+const BASIS_POINTS: u64 = 10_000;
+// A win must at least return the stake and may pay at most 3x
+const MIN_WIN_MULTIPLIER_BPS: u16 = 10_000;
+const MAX_WIN_MULTIPLIER_BPS: u16 = 30_000;
 
 declare_id!("AU68jQVHrxf1XcYPEEu3nS9d6QQpVdpQ8SUsX6NofaX4");
 
@@ -14,13 +22,65 @@ pub mod rock_paper_scissors_against_rng {
         Ok(())
     }
 
+    pub fn initialize_house(ctx: Context<InitializeHouse>, config: HouseConfig) -> Result<()> {
+        config.validate()?;
+
+        let house = &mut ctx.accounts.house;
+        house.bump = ctx.bumps.house;
+        house.authority = ctx.accounts.authority.key();
+        house.config = config;
+        house.reserved = 0;
+        Ok(())
+    }
+
+    pub fn configure_house(ctx: Context<UpdateHouse>, config: HouseConfig) -> Result<()> {
+        config.validate()?;
+        ctx.accounts.house.config = config;
+        Ok(())
+    }
+
+    pub fn fund_house(ctx: Context<FundHouse>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: ctx.accounts.house.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        Ok(())
+    }
+
+    pub fn withdraw_house(ctx: Context<UpdateHouse>, amount: u64) -> Result<()> {
+        // Lamports reserved for pending plays stay in the bankroll
+        require!(
+            amount <= available_bankroll(&ctx.accounts.house)?,
+            ErrorCode::InsufficientBankroll
+        );
+        ctx.accounts.house.sub_lamports(amount)?;
+        ctx.accounts.authority.add_lamports(amount)?;
+        Ok(())
+    }
+
     pub fn play_rps(
         ctx: Context<PlayRps>,
         computation_offset: u64,
         player_move: [u8; 32],
         pub_key: [u8; 32],
         nonce: u128,
+        wager: u64,
     ) -> Result<()> {
+        escrow_wager(
+            &mut ctx.accounts.house,
+            &mut ctx.accounts.wager,
+            ctx.bumps.wager,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            wager,
+        )?;
+
         let args = ArgBuilder::new()
             .x25519_pubkey(pub_key)
             .plaintext_u128(nonce)
@@ -36,7 +96,20 @@ pub mod rock_paper_scissors_against_rng {
             vec![PlayRpsCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[],
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.wager.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.house.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.payer.key(),
+                        is_writable: true,
+                    },
+                ],
             )?],
             1,
             0,
@@ -49,24 +122,358 @@ pub mod rock_paper_scissors_against_rng {
         ctx: Context<PlayRpsCallback>,
         output: SignedComputationOutputs<PlayRpsOutput>,
     ) -> Result<()> {
-        let o = match output.verify_output(
+        let (code, house_move) = match output.verify_output(
             &ctx.accounts.cluster_account,
             &ctx.accounts.computation_account,
         ) {
-            Ok(PlayRpsOutput { field_0 }) => field_0,
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+            Ok(PlayRpsOutput {
+                field_0:
+                    PlayRpsOutputStruct0 {
+                        field_0: result,
+                        field_1: house_move,
+                    },
+            }) => (result, house_move),
+            Err(_) => {
+                settle_wager(
+                    &mut ctx.accounts.house,
+                    &ctx.accounts.wager,
+                    &ctx.accounts.player,
+                    None,
+                )?;
+                emit!(PlayRpsRefundedEvent {
+                    player: ctx.accounts.wager.player,
+                    wager: ctx.accounts.wager.amount,
+                });
+                return Ok(());
+            }
         };
 
-        let result = match o {
-            0 => "Tie".to_string(),
-            1 => "Player wins".to_string(),
-            2 => "House wins".to_string(),
-            _ => "Invalid move".to_string(),
+        let result = RpsOutcome::from_code(code)?;
+        let paid_out = settle_wager(
+            &mut ctx.accounts.house,
+            &ctx.accounts.wager,
+            &ctx.accounts.player,
+            Some(result),
+        )?;
+
+        emit!(PlayRpsEvent {
+            result,
+            house_move,
+            player: ctx.accounts.wager.player,
+            wager: ctx.accounts.wager.amount,
+            payout: paid_out,
+        });
+        Ok(())
+    }
+
+    pub fn init_play_rpsls_comp_def(ctx: Context<InitPlayRpslsCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    // Rock-Paper-Scissors-Lizard-Spock mode, sharing the bankroll and payout table with play_rps
+    pub fn play_rpsls(
+        ctx: Context<PlayRpsls>,
+        computation_offset: u64,
+        player_move: [u8; 32],
+        pub_key: [u8; 32],
+        nonce: u128,
+        wager: u64,
+    ) -> Result<()> {
+        escrow_wager(
+            &mut ctx.accounts.house,
+            &mut ctx.accounts.wager,
+            ctx.bumps.wager,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            wager,
+        )?;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_u8(player_move)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![PlayRpslsCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.wager.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.house.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.payer.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "play_rpsls")]
+    pub fn play_rpsls_callback(
+        ctx: Context<PlayRpslsCallback>,
+        output: SignedComputationOutputs<PlayRpslsOutput>,
+    ) -> Result<()> {
+        let (code, house_move) = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(PlayRpslsOutput {
+                field_0:
+                    PlayRpslsOutputStruct0 {
+                        field_0: result,
+                        field_1: house_move,
+                    },
+            }) => (result, house_move),
+            Err(_) => {
+                settle_wager(
+                    &mut ctx.accounts.house,
+                    &ctx.accounts.wager,
+                    &ctx.accounts.player,
+                    None,
+                )?;
+                emit!(PlayRpsRefundedEvent {
+                    player: ctx.accounts.wager.player,
+                    wager: ctx.accounts.wager.amount,
+                });
+                return Ok(());
+            }
         };
 
-        emit!(PlayRpsEvent { result });
+        let result = RpsOutcome::from_code(code)?;
+        let paid_out = settle_wager(
+            &mut ctx.accounts.house,
+            &ctx.accounts.wager,
+            &ctx.accounts.player,
+            Some(result),
+        )?;
+
+        emit!(PlayRpslsEvent {
+            result,
+            house_move,
+            player: ctx.accounts.wager.player,
+            wager: ctx.accounts.wager.amount,
+            payout: paid_out,
+        });
+        Ok(())
+    }
+}
+
+/// Checks the wager against the bankroll limits and moves the stake into escrow.
+fn escrow_wager<'info>(
+    house: &mut Account<'info, House>,
+    wager_account: &mut Account<'info, Wager>,
+    bump: u8,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    wager: u64,
+) -> Result<()> {
+    require!(wager > 0, ErrorCode::InvalidWager);
+
+    // Bets are capped relative to the bankroll that is not already backing pending plays,
+    // and the house reserves its share of a winning payout so it can never go insolvent
+    let available = available_bankroll(house)?;
+    let config = house.config;
+    let max_bet = (available as u128 * config.max_bet_bps as u128 / BASIS_POINTS as u128) as u64;
+    require!(wager <= max_bet, ErrorCode::WagerTooLarge);
+
+    let payout = config.win_payout(wager)?;
+    let house_risk = payout - wager;
+    require!(house_risk <= available, ErrorCode::InsufficientBankroll);
+    house.reserved = house
+        .reserved
+        .checked_add(house_risk)
+        .ok_or(ErrorCode::Overflow)?;
+
+    wager_account.bump = bump;
+    wager_account.player = payer.key();
+    wager_account.amount = wager;
+    wager_account.payout = payout;
+
+    // The stake is escrowed in the wager account itself until the callback settles it
+    system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            system_program::Transfer {
+                from: payer.to_account_info(),
+                to: wager_account.to_account_info(),
+            },
+        ),
+        wager,
+    )
+}
+
+/// Pays out a settled play, or refunds it if the computation was aborted (`None`).
+///
+/// Returns the lamports returned to the player, stake included.
+fn settle_wager<'info>(
+    house: &mut Account<'info, House>,
+    wager_account: &Account<'info, Wager>,
+    player: &SystemAccount<'info>,
+    result: Option<RpsOutcome>,
+) -> Result<u64> {
+    let wager = wager_account.amount;
+    let payout = wager_account.payout;
+    let house_risk = payout - wager;
+
+    // The reservation is released whatever the outcome. The wager account is closed to
+    // the player on exit, so anything left in it (stake included) goes back to them.
+    house.reserved = house.reserved.saturating_sub(house_risk);
+
+    let house_takes_stake = match result {
+        Some(RpsOutcome::HouseWins) => true,
+        Some(RpsOutcome::InvalidMove) => !house.config.refund_invalid_moves,
+        _ => false,
+    };
+
+    if result == Some(RpsOutcome::PlayerWins) {
+        house.sub_lamports(house_risk)?;
+        player.add_lamports(house_risk)?;
+        Ok(payout)
+    } else if house_takes_stake {
+        wager_account.sub_lamports(wager)?;
+        house.add_lamports(wager)?;
+        Ok(0)
+    } else {
+        Ok(wager)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RpsOutcome {
+    Tie,
+    PlayerWins,
+    HouseWins,
+    InvalidMove,
+}
+
+impl RpsOutcome {
+    /// Maps the result code revealed by the `play_rps` and `play_rpsls` circuits.
+    fn from_code(code: u8) -> Result<Self> {
+        match code {
+            0 => Ok(RpsOutcome::Tie),
+            1 => Ok(RpsOutcome::PlayerWins),
+            2 => Ok(RpsOutcome::HouseWins),
+            3 => Ok(RpsOutcome::InvalidMove),
+            _ => Err(ErrorCode::InvalidGameResult.into()),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct HouseConfig {
+    /// Total paid to the player on a win, stake included, in basis points of the wager
+    pub win_multiplier_bps: u16,
+    /// Largest accepted wager, in basis points of the available bankroll
+    pub max_bet_bps: u16,
+    /// Whether an invalid move gets its stake back instead of forfeiting it to the house
+    pub refund_invalid_moves: bool,
+}
+
+impl HouseConfig {
+    fn validate(&self) -> Result<()> {
+        require!(
+            (MIN_WIN_MULTIPLIER_BPS..=MAX_WIN_MULTIPLIER_BPS).contains(&self.win_multiplier_bps),
+            ErrorCode::InvalidHouseConfig
+        );
+        require!(
+            self.max_bet_bps > 0 && self.max_bet_bps as u64 <= BASIS_POINTS,
+            ErrorCode::InvalidHouseConfig
+        );
         Ok(())
     }
+
+    fn win_payout(&self, wager: u64) -> Result<u64> {
+        let payout = wager as u128 * self.win_multiplier_bps as u128 / BASIS_POINTS as u128;
+        u64::try_from(payout).map_err(|_| ErrorCode::Overflow.into())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct House {
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub config: HouseConfig,
+    /// Lamports backing the winning payouts of plays still waiting on their result
+    pub reserved: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Wager {
+    pub bump: u8,
+    pub player: Pubkey,
+    pub amount: u64,
+    /// Total owed to the player, stake included, if they win
+    pub payout: u64,
+}
+
+/// Bankroll lamports not needed for rent exemption or pending payouts.
+fn available_bankroll(house: &Account<House>) -> Result<u64> {
+    let rent = Rent::get()?.minimum_balance(8 + House::INIT_SPACE);
+    Ok(house
+        .get_lamports()
+        .saturating_sub(rent)
+        .saturating_sub(house.reserved))
+}
+
+#[derive(Accounts)]
+pub struct InitializeHouse<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + House::INIT_SPACE,
+        seeds = [b"house"],
+        bump,
+    )]
+    pub house: Account<'info, House>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateHouse<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"house"],
+        bump = house.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub house: Account<'info, House>,
+}
+
+#[derive(Accounts)]
+pub struct FundHouse<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"house"],
+        bump = house.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub house: Account<'info, House>,
+    pub system_program: Program<'info, System>,
 }
 
 #[queue_computation_accounts("play_rps", payer)]
@@ -75,6 +482,20 @@ pub mod rock_paper_scissors_against_rng {
 pub struct PlayRps<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"house"],
+        bump = house.bump,
+    )]
+    pub house: Box<Account<'info, House>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Wager::INIT_SPACE,
+        seeds = [b"wager", computation_offset.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub wager: Box<Account<'info, Wager>>,
     #[account(
         init_if_needed,
         space = 9,
@@ -150,6 +571,20 @@ pub struct PlayRpsCallback<'info> {
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar, checked by the account constraint
     pub instructions_sysvar: AccountInfo<'info>,
+    #[account(
+        mut,
+        has_one = player,
+        close = player,
+    )]
+    pub wager: Account<'info, Wager>,
+    #[account(
+        mut,
+        seeds = [b"house"],
+        bump = house.bump,
+    )]
+    pub house: Account<'info, House>,
+    #[account(mut)]
+    pub player: SystemAccount<'info>,
 }
 
 #[init_computation_definition_accounts("play_rps", payer)]
@@ -176,9 +611,167 @@ pub struct InitPlayRpsCompDef<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[queue_computation_accounts("play_rpsls", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PlayRpsls<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"house"],
+        bump = house.bump,
+    )]
+    pub house: Box<Account<'info, House>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Wager::INIT_SPACE,
+        seeds = [b"wager", computation_offset.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub wager: Box<Account<'info, Wager>>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_RPSLS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        mut,
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("play_rpsls")]
+#[derive(Accounts)]
+pub struct PlayRpslsCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_RPSLS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(
+        mut,
+        has_one = player,
+        close = player,
+    )]
+    pub wager: Account<'info, Wager>,
+    #[account(
+        mut,
+        seeds = [b"house"],
+        bump = house.bump,
+    )]
+    pub house: Account<'info, House>,
+    #[account(mut)]
+    pub player: SystemAccount<'info>,
+}
+
+#[init_computation_definition_accounts("play_rpsls", payer)]
+#[derive(Accounts)]
+pub struct InitPlayRpslsCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table, checked by arcium program.
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program is the Address Lookup Table program.
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
 #[event]
 pub struct PlayRpsEvent {
-    pub result: String,
+    pub result: RpsOutcome,
+    /// Move the house played (0 - Rock, 1 - Paper, 2 - Scissors), for auditing the result
+    pub house_move: u8,
+    pub player: Pubkey,
+    pub wager: u64,
+    /// Lamports returned to the player, stake included (0 if the stake was lost)
+    pub payout: u64,
+}
+
+#[event]
+pub struct PlayRpslsEvent {
+    pub result: RpsOutcome,
+    /// Move the house played (0 - Rock, 1 - Paper, 2 - Scissors, 3 - Lizard, 4 - Spock)
+    pub house_move: u8,
+    pub player: Pubkey,
+    pub wager: u64,
+    /// Lamports returned to the player, stake included (0 if the stake was lost)
+    pub payout: u64,
+}
+
+#[event]
+pub struct PlayRpsRefundedEvent {
+    pub player: Pubkey,
+    pub wager: u64,
 }
 
 #[error_code]
@@ -187,4 +780,18 @@ pub enum ErrorCode {
     AbortedComputation,
     #[msg("Cluster not set")]
     ClusterNotSet,
+    #[msg("Invalid house configuration")]
+    InvalidHouseConfig,
+    #[msg("Wager must be greater than zero")]
+    InvalidWager,
+    #[msg("Wager exceeds the maximum bet for the current bankroll")]
+    WagerTooLarge,
+    #[msg("House bankroll cannot cover this payout")]
+    InsufficientBankroll,
+    #[msg("Not authorized to perform this action")]
+    Unauthorized,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Unexpected game result value from computation")]
+    InvalidGameResult,
 }
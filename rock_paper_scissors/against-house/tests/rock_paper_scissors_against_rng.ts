@@ -1,6 +1,6 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
-import { PublicKey } from "@solana/web3.js";
+import { LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
 import { RockPaperScissorsAgainstRng } from "../target/types/rock_paper_scissors_against_rng";
 import { randomBytes } from "crypto";
 import {
@@ -25,6 +25,7 @@ import {
 } from "@arcium-hq/client";
 import * as fs from "fs";
 import * as os from "os";
+import { expect } from "chai";
 
 describe("RockPaperScissorsAgainstRng", () => {
   anchor.setProvider(anchor.AnchorProvider.env());
@@ -73,12 +74,30 @@ describe("RockPaperScissorsAgainstRng", () => {
       initRpsSig
     );
 
+    // Wins pay 1.9x the stake, bets are capped at 5% of the bankroll and
+    // invalid moves forfeit their stake
+    await program.methods
+      .initializeHouse({
+        winMultiplierBps: 19000,
+        maxBetBps: 500,
+        refundInvalidMoves: false,
+      })
+      .accounts({ authority: owner.publicKey })
+      .signers([owner])
+      .rpc({ commitment: "confirmed" });
+
+    await program.methods
+      .fundHouse(new anchor.BN(LAMPORTS_PER_SOL))
+      .accounts({ authority: owner.publicKey })
+      .signers([owner])
+      .rpc({ commitment: "confirmed" });
+
     const privateKey = x25519.utils.randomSecretKey();
     const publicKey = x25519.getPublicKey(privateKey);
     const sharedSecret = x25519.getSharedSecret(privateKey, mxePublicKey);
     const cipher = new RescueCipher(sharedSecret);
 
-    const playerMove = BigInt(2); // 1 = rock, 2 = paper, 3 = scissors
+    const playerMove = BigInt(2); // 0 = rock, 1 = paper, 2 = scissors
     const plaintext = [playerMove];
 
     const nonce = randomBytes(16);
@@ -87,13 +106,15 @@ describe("RockPaperScissorsAgainstRng", () => {
     const playRpsEventPromise = awaitEvent("playRpsEvent");
 
     const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    const wager = new anchor.BN(LAMPORTS_PER_SOL / 100);
 
     const queueSig = await program.methods
       .playRps(
         computationOffset,
         Array.from(ciphertext[0]),
         Array.from(publicKey),
-        new anchor.BN(deserializeLE(nonce).toString())
+        new anchor.BN(deserializeLE(nonce).toString()),
+        wager
       )
       .accountsPartial({
         computationAccount: getComputationAccAddress(
@@ -127,7 +148,144 @@ describe("RockPaperScissorsAgainstRng", () => {
 
     const playRpsEvent = await playRpsEventPromise;
 
-    console.log(playRpsEvent.result);
+    const result = Object.keys(playRpsEvent.result)[0];
+    console.log(result, "paid out", playRpsEvent.payout.toString());
+
+    // Check the revealed house move against the result
+    const moveNames = ["Rock", "Paper", "Scissors"];
+    console.log("House played", moveNames[playRpsEvent.houseMove]);
+    expect(playRpsEvent.houseMove).to.be.lessThan(3);
+    const player = Number(playerMove);
+    const house = playRpsEvent.houseMove;
+    const expectedResult =
+      player > 2
+        ? "invalidMove"
+        : player === house
+        ? "tie"
+        : (player + 3 - house) % 3 === 1
+        ? "playerWins"
+        : "houseWins";
+    expect(result).to.equal(expectedResult);
+
+    const expectedPayout = {
+      tie: wager,
+      playerWins: wager.muln(19000).divn(10000),
+      houseWins: new anchor.BN(0),
+      invalidMove: new anchor.BN(0),
+    }[result];
+    expect(playRpsEvent.payout.toString()).to.equal(expectedPayout.toString());
+  });
+
+  it("play rpsls against rng", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+
+    console.log("Initializing play rpsls computation definition");
+    const initRpslsSig = await initPlayRpslsCompDef(program, owner);
+    console.log(
+      "Play rpsls computation definition initialized with signature",
+      initRpslsSig
+    );
+
+    // The house was set up and funded by the previous test
+
+    const privateKey = x25519.utils.randomSecretKey();
+    const publicKey = x25519.getPublicKey(privateKey);
+    const sharedSecret = x25519.getSharedSecret(privateKey, mxePublicKey);
+    const cipher = new RescueCipher(sharedSecret);
+
+    // 0 = rock, 1 = paper, 2 = scissors, 3 = lizard, 4 = spock
+    const playerMove = BigInt(4);
+    const plaintext = [playerMove];
+
+    const nonce = randomBytes(16);
+    const ciphertext = cipher.encrypt(plaintext, nonce);
+
+    const playRpslsEventPromise = awaitEvent("playRpslsEvent");
+
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    const wager = new anchor.BN(LAMPORTS_PER_SOL / 100);
+
+    const queueSig = await program.methods
+      .playRpsls(
+        computationOffset,
+        Array.from(ciphertext[0]),
+        Array.from(publicKey),
+        new anchor.BN(deserializeLE(nonce).toString()),
+        wager
+      )
+      .accountsPartial({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        clusterAccount: clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("play_rpsls")).readUInt32LE()
+        ),
+      })
+      .rpc({
+        skipPreflight: true,
+        commitment: "confirmed",
+      });
+    console.log("Queue sig is ", queueSig);
+
+    const finalizeSig = await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+    console.log("Finalize sig is ", finalizeSig);
+
+    const playRpslsEvent = await playRpslsEventPromise;
+
+    const result = Object.keys(playRpslsEvent.result)[0];
+    console.log(result, "paid out", playRpslsEvent.payout.toString());
+
+    // Check the revealed house move against the result
+    const moveNames = ["Rock", "Paper", "Scissors", "Lizard", "Spock"];
+    console.log("House played", moveNames[playRpslsEvent.houseMove]);
+    expect(playRpslsEvent.houseMove).to.be.lessThan(5);
+    // Moves each one beats, indexed by move
+    const beats = [
+      [2, 3],
+      [0, 4],
+      [1, 3],
+      [1, 4],
+      [0, 2],
+    ];
+    const player = Number(playerMove);
+    const house = playRpslsEvent.houseMove;
+    const expectedResult =
+      player > 4
+        ? "invalidMove"
+        : player === house
+        ? "tie"
+        : beats[player].includes(house)
+        ? "playerWins"
+        : "houseWins";
+    expect(result).to.equal(expectedResult);
+
+    const expectedPayout = {
+      tie: wager,
+      playerWins: wager.muln(19000).divn(10000),
+      houseWins: new anchor.BN(0),
+      invalidMove: new anchor.BN(0),
+    }[result];
+    expect(playRpslsEvent.payout.toString()).to.equal(
+      expectedPayout.toString()
+    );
   });
 
   async function initPlayRpsCompDef(
@@ -180,6 +338,57 @@ describe("RockPaperScissorsAgainstRng", () => {
 
     return sig;
   }
+
+  async function initPlayRpslsCompDef(
+    program: Program<RockPaperScissorsAgainstRng>,
+    owner: anchor.web3.Keypair
+  ): Promise<string> {
+    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+      "ComputationDefinitionAccount"
+    );
+    const offset = getCompDefAccOffset("play_rpsls");
+
+    const compDefPDA = PublicKey.findProgramAddressSync(
+      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+      getArciumProgramId()
+    )[0];
+
+    console.log("Comp def pda is ", compDefPDA.toBase58());
+
+    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
+    const mxeAccount = getMXEAccAddress(program.programId);
+    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+    const lutAddress = getLookupTableAddress(
+      program.programId,
+      mxeAcc.lutOffsetSlot
+    );
+
+    const sig = await program.methods
+      .initPlayRpslsCompDef()
+      .accounts({
+        compDefAccount: compDefPDA,
+        payer: owner.publicKey,
+        mxeAccount,
+        addressLookupTable: lutAddress,
+      })
+      .signers([owner])
+      .rpc({
+        preflightCommitment: "confirmed",
+        commitment: "confirmed",
+      });
+    console.log("Init play rpsls computation definition transaction", sig);
+
+    const rawCircuit = fs.readFileSync("build/play_rpsls.arcis");
+    await uploadCircuit(
+      provider as anchor.AnchorProvider,
+      "play_rpsls",
+      program.programId,
+      rawCircuit,
+      true
+    );
+
+    return sig;
+  }
 });
 
 async function getMXEPublicKeyWithRetry(
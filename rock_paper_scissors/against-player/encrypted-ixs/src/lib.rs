@@ -21,34 +21,41 @@ mod circuits {
     }
 
     pub struct PlayersMove {
-        player: u8,
         player_move: u8,
     }
 
+    // The player slot (0 - player A, 1 - player B) is derived from the signer by the program,
+    // so a player can only ever fill their own slot.
+    // Returns the updated moves, plus whether each player now has a move recorded
     #[instruction]
     pub fn player_move(
         players_move_ctxt: Enc<Shared, PlayersMove>,
         game_ctxt: Enc<Mxe, GameMoves>,
-    ) -> Enc<Mxe, GameMoves> {
+        player: u8,
+    ) -> (Enc<Mxe, GameMoves>, bool, bool) {
         let players_move = players_move_ctxt.to_arcis();
         let mut game_moves = game_ctxt.to_arcis();
 
-        // Check which player is moving, if the player hasn't played their move yet, and the move is valid
-        if players_move.player == 0 && game_moves.player_a_move == 3 && players_move.player_move < 3
-        {
+        // Check if the player hasn't played their move yet, and the move is valid
+        if player == 0 && game_moves.player_a_move == 3 && players_move.player_move < 3 {
             game_moves.player_a_move = players_move.player_move;
-        } else if players_move.player == 1
-            && game_moves.player_b_move == 3
-            && players_move.player_move < 3
-        {
+        } else if player == 1 && game_moves.player_b_move == 3 && players_move.player_move < 3 {
             game_moves.player_b_move = players_move.player_move;
         }
 
-        game_ctxt.owner.from_arcis(game_moves)
+        let player_a_moved = game_moves.player_a_move != 3;
+        let player_b_moved = game_moves.player_b_move != 3;
+
+        (
+            game_ctxt.owner.from_arcis(game_moves),
+            player_a_moved.reveal(),
+            player_b_moved.reveal(),
+        )
     }
 
+    // Returns the round result, and the moves reset to the sentinel for the next round
     #[instruction]
-    pub fn compare_moves(game_ctxt: Enc<Mxe, GameMoves>) -> u8 {
+    pub fn compare_moves(game_ctxt: Enc<Mxe, GameMoves>) -> (u8, Enc<Mxe, GameMoves>) {
         let game_moves = game_ctxt.to_arcis();
 
         // 0 - tie, 1 - player A wins, 2 - player B wins, 3 - invalid move
@@ -66,6 +73,22 @@ mod circuits {
             2 // Player B wins
         };
 
-        result.reveal()
+        let next_round = GameMoves {
+            player_a_move: 3,
+            player_b_move: 3,
+        };
+
+        (result.reveal(), game_ctxt.owner.from_arcis(next_round))
+    }
+
+    // Reveals only whether each player has a move recorded, used to settle timeouts
+    #[instruction]
+    pub fn check_moves(game_ctxt: Enc<Mxe, GameMoves>) -> (bool, bool) {
+        let game_moves = game_ctxt.to_arcis();
+
+        (
+            (game_moves.player_a_move != 3).reveal(),
+            (game_moves.player_b_move != 3).reveal(),
+        )
     }
 }
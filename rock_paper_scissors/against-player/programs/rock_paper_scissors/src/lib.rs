@@ -1,10 +1,27 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
 use arcium_anchor::prelude::*;
 use arcium_client::idl::arcium::types::CallbackAccount;
 
 const COMP_DEF_OFFSET_INIT_GAME: u32 = comp_def_offset("init_game");
 const COMP_DEF_OFFSET_PLAYER_MOVE: u32 = comp_def_offset("player_move");
 const COMP_DEF_OFFSET_COMPARE_MOVES: u32 = comp_def_offset("compare_moves");
+const COMP_DEF_OFFSET_CHECK_MOVES: u32 = comp_def_offset("check_moves");
+
+/// Longest match that can be played (best of 7)
+const MAX_BEST_OF: u8 = 7;
+/// Rounds kept in the match history. Ties and invalid rounds don't score, so a match
+/// that reaches this many rounds without a majority ends with the current leader.
+const MAX_ROUNDS: usize = 16;
+/// Open games the lobby can list at once
+const MAX_OPEN_GAMES: usize = 32;
+/// Seconds a lobby game stays listed before anyone can prune it
+const LOBBY_LISTING_SECONDS: i64 = 60 * 60;
+const BASIS_POINTS: u64 = 10_000;
+/// Highest protocol fee that can be configured (10%)
+const MAX_PROTOCOL_FEE_BPS: u16 = 1_000;
+/// Largest tournament bracket
+const MAX_ENTRANTS: usize = 16;
 
 declare_id!("6FasviktxsUBZBst1sAA5uEN2WVnW7MuSiP6hiY9g1XT");
 
@@ -17,17 +34,63 @@ pub mod rock_paper_scissors {
         Ok(())
     }
 
+    /// Sets up the protocol fee taken from staked games and the treasury it is paid to.
+    ///
+    /// The treasury must already be rent-exempt: fees can be smaller than the rent-exempt
+    /// minimum, and crediting them to an unfunded account would fail every settlement.
+    pub fn initialize_config(ctx: Context<InitializeConfig>, fee_bps: u16) -> Result<()> {
+        require!(
+            fee_bps <= MAX_PROTOCOL_FEE_BPS,
+            ErrorCode::InvalidProtocolFee
+        );
+        require_rent_exempt(&ctx.accounts.treasury)?;
+
+        let config = &mut ctx.accounts.config;
+        config.bump = ctx.bumps.config;
+        config.authority = ctx.accounts.authority.key();
+        config.treasury = ctx.accounts.treasury.key();
+        config.fee_bps = fee_bps;
+        Ok(())
+    }
+
+    /// Updates the fee and treasury. Games already created keep the terms they started with.
+    pub fn update_config(ctx: Context<UpdateConfig>, fee_bps: u16) -> Result<()> {
+        require!(
+            fee_bps <= MAX_PROTOCOL_FEE_BPS,
+            ErrorCode::InvalidProtocolFee
+        );
+        require_rent_exempt(&ctx.accounts.treasury)?;
+
+        let config = &mut ctx.accounts.config;
+        config.treasury = ctx.accounts.treasury.key();
+        config.fee_bps = fee_bps;
+        Ok(())
+    }
+
     pub fn init_game(
         ctx: Context<InitGame>,
         computation_offset: u64,
         id: u64,
         player_a: Pubkey,
         player_b: Pubkey,
+        stake: u64,
+        best_of: u8,
+        move_timeout: i64,
     ) -> Result<()> {
+        ctx.accounts.vault.bump = ctx.bumps.vault;
+
         let game = &mut ctx.accounts.rps_game;
-        game.id = id;
-        game.player_a = player_a;
+        game.setup(
+            id,
+            player_a,
+            stake,
+            best_of,
+            move_timeout,
+            &ctx.accounts.config,
+        )?;
         game.player_b = player_b;
+        game.state = GameState::AwaitingMoves;
+        game.start_move_window()?;
 
         let args = ArgBuilder::new().build();
 
@@ -76,6 +139,334 @@ pub mod rock_paper_scissors {
         Ok(())
     }
 
+    /// Opens a lobby game with the creator as player A, waiting for anyone to join as player B.
+    pub fn create_game(
+        ctx: Context<CreateGame>,
+        computation_offset: u64,
+        id: u64,
+        stake: u64,
+        best_of: u8,
+        move_timeout: i64,
+    ) -> Result<()> {
+        ctx.accounts.vault.bump = ctx.bumps.vault;
+
+        let game = &mut ctx.accounts.rps_game;
+        game.setup(
+            id,
+            ctx.accounts.payer.key(),
+            stake,
+            best_of,
+            move_timeout,
+            &ctx.accounts.config,
+        )?;
+        game.state = GameState::Open;
+
+        // The creator's stake goes into escrow straight away
+        deposit_stake(
+            &ctx.accounts.payer,
+            &ctx.accounts.vault,
+            &ctx.accounts.system_program,
+            stake,
+        )?;
+        ctx.accounts.rps_game.stakes_deposited[0] = true;
+
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.lobby.list(ctx.accounts.rps_game.key(), now)?;
+
+        let args = ArgBuilder::new().build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![InitGameCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.rps_game.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn join_game(ctx: Context<JoinGame>) -> Result<()> {
+        let game = &mut ctx.accounts.rps_game;
+        game.require_state(GameState::Open)?;
+        require!(
+            ctx.accounts.player.key() != game.player_a,
+            ErrorCode::CannotJoinOwnGame
+        );
+        // Only games still listed can be joined; an expired one can only be cancelled
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.lobby.take(&game.key(), now)?;
+
+        game.player_b = ctx.accounts.player.key();
+        game.state = GameState::AwaitingMoves;
+        game.stakes_deposited[1] = true;
+        game.start_move_window()?;
+
+        deposit_stake(
+            &ctx.accounts.player,
+            &ctx.accounts.vault,
+            &ctx.accounts.system_program,
+            game.stake,
+        )?;
+
+        emit!(GameJoinedEvent {
+            id: game.id,
+            player_a: game.player_a,
+            player_b: game.player_b,
+        });
+        Ok(())
+    }
+
+    /// Cancels an open game nobody has joined, returning its stake and rent to the creator.
+    pub fn cancel_game(ctx: Context<CancelGame>) -> Result<()> {
+        ctx.accounts.rps_game.require_state(GameState::Open)?;
+        ctx.accounts.lobby.remove(&ctx.accounts.rps_game.key());
+        Ok(())
+    }
+
+    /// Delists every lobby game whose listing has expired, freeing its slot.
+    ///
+    /// Anyone can call this. Delisted games keep their escrowed stake until the creator
+    /// cancels them.
+    pub fn prune_lobby(ctx: Context<PruneLobby>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.lobby.prune(now);
+        Ok(())
+    }
+
+    /// Opens registration for a single-elimination tournament of `size` entrants.
+    ///
+    /// Entry fees make up the prize pool, split between the champion and the runner-up
+    /// according to `prize_shares_bps`.
+    pub fn create_tournament(
+        ctx: Context<CreateTournament>,
+        id: u64,
+        size: u8,
+        entry_fee: u64,
+        best_of: u8,
+        move_timeout: i64,
+        prize_shares_bps: [u16; 2],
+    ) -> Result<()> {
+        require!(
+            size >= 2 && size.is_power_of_two() && size as usize <= MAX_ENTRANTS,
+            ErrorCode::InvalidTournamentSize
+        );
+        check_match_terms(best_of, move_timeout)?;
+        require!(
+            prize_shares_bps[0] as u64 + prize_shares_bps[1] as u64 == BASIS_POINTS
+                && prize_shares_bps[0] >= prize_shares_bps[1],
+            ErrorCode::InvalidPrizeShares
+        );
+
+        let tournament = &mut ctx.accounts.tournament;
+        tournament.bump = ctx.bumps.tournament;
+        tournament.organizer = ctx.accounts.organizer.key();
+        tournament.id = id;
+        tournament.size = size;
+        tournament.entry_fee = entry_fee;
+        tournament.best_of = best_of;
+        tournament.move_timeout = move_timeout;
+        tournament.prize_shares_bps = prize_shares_bps;
+        tournament.state = TournamentState::Registration;
+        Ok(())
+    }
+
+    /// Enters the signer into a tournament, paying the entry fee into the prize pool.
+    pub fn register(ctx: Context<Register>) -> Result<()> {
+        let entrant = ctx.accounts.entrant.key();
+        let tournament = &mut ctx.accounts.tournament;
+        require!(
+            tournament.state == TournamentState::Registration,
+            ErrorCode::InvalidTournamentState
+        );
+        require!(
+            !tournament.entrants.contains(&entrant),
+            ErrorCode::AlreadyRegistered
+        );
+
+        tournament.entrants.push(entrant);
+        if tournament.entrants.len() == tournament.size as usize {
+            tournament.state = TournamentState::Seeding;
+        }
+
+        if tournament.entry_fee > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.entrant.to_account_info(),
+                        to: ctx.accounts.tournament.to_account_info(),
+                    },
+                ),
+                ctx.accounts.tournament.entry_fee,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Calls off a tournament whose final isn't decided yet, so entrants can take their fees
+    /// back.
+    ///
+    /// Bracket games still being played can't advance anyone once the tournament is
+    /// cancelled.
+    pub fn cancel_tournament(ctx: Context<CancelTournament>) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        require!(
+            matches!(
+                tournament.state,
+                TournamentState::Registration
+                    | TournamentState::Seeding
+                    | TournamentState::InProgress
+            ),
+            ErrorCode::InvalidTournamentState
+        );
+
+        tournament.state = TournamentState::Cancelled;
+        Ok(())
+    }
+
+    /// Refunds the signer's entry fee from a cancelled tournament.
+    pub fn withdraw_entry(ctx: Context<WithdrawEntry>) -> Result<()> {
+        let entrant = ctx.accounts.entrant.key();
+        let tournament = &mut ctx.accounts.tournament;
+        require!(
+            tournament.state == TournamentState::Cancelled,
+            ErrorCode::InvalidTournamentState
+        );
+        let position = tournament
+            .entrants
+            .iter()
+            .position(|registered| *registered == entrant)
+            .ok_or(ErrorCode::NotRegistered)?;
+        tournament.entrants.remove(position);
+
+        let entry_fee = tournament.entry_fee;
+        tournament.sub_lamports(entry_fee)?;
+        ctx.accounts.entrant.add_lamports(entry_fee)?;
+        Ok(())
+    }
+
+    /// Creates one game of a full tournament's bracket.
+    ///
+    /// Matches are numbered like a binary heap: match 1 is the final and the winners of
+    /// matches `2m` and `2m + 1` meet in match `m`. First-round matches are seeded with
+    /// entrants in registration order and start straight away; later matches wait for the
+    /// winners to advance into them. The tournament starts once every match exists.
+    ///
+    /// Anyone can create a match once the bracket is full, paying its rent, so a stalled
+    /// organizer can't hold the tournament up.
+    pub fn create_bracket_game(
+        ctx: Context<CreateBracketGame>,
+        computation_offset: u64,
+        match_index: u8,
+    ) -> Result<()> {
+        let tournament = &ctx.accounts.tournament;
+        require!(
+            tournament.state == TournamentState::Seeding,
+            ErrorCode::InvalidTournamentState
+        );
+        require!(
+            match_index >= 1 && match_index < tournament.size,
+            ErrorCode::InvalidBracketGame
+        );
+
+        ctx.accounts.vault.bump = ctx.bumps.vault;
+
+        let game = &mut ctx.accounts.rps_game;
+        game.setup(
+            match_index as u64,
+            Pubkey::default(),
+            0,
+            tournament.best_of,
+            tournament.move_timeout,
+            &ctx.accounts.config,
+        )?;
+        game.tournament = Some(tournament.key());
+        game.match_index = match_index;
+
+        let first_round = tournament.size / 2;
+        if match_index >= first_round {
+            let seed = 2 * (match_index - first_round) as usize;
+            game.player_a = tournament.entrants[seed];
+            game.player_b = tournament.entrants[seed + 1];
+            game.state = GameState::AwaitingMoves;
+            game.start_move_window()?;
+        } else {
+            game.state = GameState::AwaitingPlayers;
+        }
+
+        let tournament = &mut ctx.accounts.tournament;
+        tournament.games_created += 1;
+        if tournament.games_created == tournament.size - 1 {
+            tournament.state = TournamentState::InProgress;
+        }
+
+        let args = ArgBuilder::new().build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![InitGameCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.rps_game.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Pays the prize pool of a finished tournament to its champion and runner-up.
+    ///
+    /// Anyone can call it, since the payees and amounts are already fixed on chain.
+    pub fn distribute_prizes(ctx: Context<DistributePrizes>) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        require!(
+            tournament.state == TournamentState::Finished,
+            ErrorCode::InvalidTournamentState
+        );
+
+        let pool = tournament
+            .entry_fee
+            .checked_mul(tournament.size as u64)
+            .ok_or(ErrorCode::Overflow)?;
+        let runner_up_prize =
+            (pool as u128 * tournament.prize_shares_bps[1] as u128 / BASIS_POINTS as u128) as u64;
+        let champion_prize = pool - runner_up_prize;
+        tournament.state = TournamentState::PrizesPaid;
+
+        tournament.sub_lamports(pool)?;
+        ctx.accounts.champion.add_lamports(champion_prize)?;
+        ctx.accounts.runner_up.add_lamports(runner_up_prize)?;
+
+        emit!(PrizesDistributedEvent {
+            tournament: tournament.key(),
+            champion: ctx.accounts.champion.key(),
+            champion_prize,
+            runner_up: ctx.accounts.runner_up.key(),
+            runner_up_prize,
+        });
+        Ok(())
+    }
+
     pub fn init_player_move_comp_def(ctx: Context<InitPlayerMoveCompDef>) -> Result<()> {
         init_comp_def(ctx.accounts, None, None)?;
         Ok(())
@@ -84,24 +475,42 @@ pub mod rock_paper_scissors {
     pub fn player_move(
         ctx: Context<PlayerMove>,
         computation_offset: u64,
-        player_id: [u8; 32],
         player_move: [u8; 32],
         pub_key: [u8; 32],
         nonce: u128,
     ) -> Result<()> {
+        let game = &ctx.accounts.rps_game;
+        // The slot comes from the signer, never from the client
+        let slot = game.slot_of(&ctx.accounts.payer.key())?;
+        game.require_state(GameState::AwaitingMoves)?;
         require!(
-            ctx.accounts.payer.key() == ctx.accounts.rps_game.player_a
-                || ctx.accounts.payer.key() == ctx.accounts.rps_game.player_b,
-            ErrorCode::NotAuthorized
+            !game.moves_submitted[slot as usize],
+            ErrorCode::MoveAlreadySubmitted
+        );
+        require!(
+            Clock::get()?.unix_timestamp <= game.move_deadline,
+            ErrorCode::MoveDeadlinePassed
         );
 
+        // Players of a direct game put their stake up with their first move
+        if !game.stakes_deposited[slot as usize] {
+            deposit_stake(
+                &ctx.accounts.payer,
+                &ctx.accounts.vault,
+                &ctx.accounts.system_program,
+                game.stake,
+            )?;
+            ctx.accounts.rps_game.stakes_deposited[slot as usize] = true;
+        }
+
+        let game = &ctx.accounts.rps_game;
         let args = ArgBuilder::new()
             .x25519_pubkey(pub_key)
             .plaintext_u128(nonce)
-            .encrypted_u8(player_id)
             .encrypted_u8(player_move)
-            .plaintext_u128(ctx.accounts.rps_game.nonce)
-            .account(ctx.accounts.rps_game.key(), 8, 32 * 2)
+            .plaintext_u128(game.nonce)
+            .account(game.key(), 8, 32 * 2)
+            .plaintext_u8(slot)
             .build();
 
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
@@ -129,11 +538,18 @@ pub mod rock_paper_scissors {
         ctx: Context<PlayerMoveCallback>,
         output: SignedComputationOutputs<PlayerMoveOutput>,
     ) -> Result<()> {
-        let o = match output.verify_output(
+        let (o, player_a_moved, player_b_moved) = match output.verify_output(
             &ctx.accounts.cluster_account,
             &ctx.accounts.computation_account,
         ) {
-            Ok(PlayerMoveOutput { field_0 }) => field_0,
+            Ok(PlayerMoveOutput {
+                field_0:
+                    PlayerMoveOutputStruct0 {
+                        field_0: moves,
+                        field_1: player_a_moved,
+                        field_2: player_b_moved,
+                    },
+            }) => (moves, player_a_moved, player_b_moved),
             Err(_) => return Err(ErrorCode::AbortedComputation.into()),
         };
 
@@ -145,6 +561,14 @@ pub mod rock_paper_scissors {
         game.moves = moves;
         game.nonce = nonce;
 
+        // A timeout may have ended the match while this move was queued
+        if game.state == GameState::AwaitingMoves {
+            game.moves_submitted = [player_a_moved, player_b_moved];
+            if player_a_moved && player_b_moved {
+                game.state = GameState::ReadyToCompare;
+            }
+        }
+
         Ok(())
     }
 
@@ -154,6 +578,20 @@ pub mod rock_paper_scissors {
     }
 
     pub fn compare_moves(ctx: Context<CompareMoves>, computation_offset: u64) -> Result<()> {
+        require!(
+            ctx.accounts.payer.key() == ctx.accounts.rps_game.player_a
+                || ctx.accounts.payer.key() == ctx.accounts.rps_game.player_b,
+            ErrorCode::NotAuthorized
+        );
+        ctx.accounts
+            .rps_game
+            .require_state(GameState::ReadyToCompare)?;
+        let (tournament, next_game) = bracket_accounts(
+            &ctx.accounts.rps_game,
+            &ctx.accounts.tournament,
+            &ctx.accounts.next_game,
+        )?;
+
         let args = ArgBuilder::new()
             .plaintext_u128(ctx.accounts.rps_game.nonce)
             .account(ctx.accounts.rps_game.key(), 8, 32 * 2)
@@ -167,7 +605,12 @@ pub mod rock_paper_scissors {
             vec![CompareMovesCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[],
+                &settlement_accounts(
+                    &ctx.accounts.rps_game,
+                    ctx.accounts.vault.key(),
+                    tournament,
+                    next_game,
+                ),
             )?],
             1,
             0,
@@ -180,33 +623,672 @@ pub mod rock_paper_scissors {
         ctx: Context<CompareMovesCallback>,
         output: SignedComputationOutputs<CompareMovesOutput>,
     ) -> Result<()> {
-        let result = match output.verify_output(
+        let (result, next_moves) = match output.verify_output(
             &ctx.accounts.cluster_account,
             &ctx.accounts.computation_account,
         ) {
-            Ok(CompareMovesOutput { field_0 }) => field_0,
+            Ok(CompareMovesOutput {
+                field_0:
+                    CompareMovesOutputStruct0 {
+                        field_0: result,
+                        field_1: next_moves,
+                    },
+            }) => (result, next_moves),
             Err(_) => return Err(ErrorCode::AbortedComputation.into()),
         };
 
-        let result_str = match result {
-            0 => "Tie",
-            1 => "Player A Wins",
-            2 => "Player B Wins",
-            3 => "Invalid Move",
-            _ => "Unknown",
-        };
+        let result = RpsOutcome::from_code(result)?;
+
+        let game = &mut ctx.accounts.rps_game;
+        // Only the first comparison queued for a round counts
+        if game.state != GameState::ReadyToCompare {
+            return Ok(());
+        }
+
+        // The circuit hands back both moves reset to the sentinel, ready for the next round
+        game.moves = next_moves.ciphertexts;
+        game.nonce = next_moves.nonce;
+        game.moves_submitted = [false, false];
+        game.state = GameState::AwaitingMoves;
+        let round = game.rounds_played + 1;
+        game.record_round(result);
+        // Each round gets a fresh move window
+        game.start_move_window()?;
+
+        emit!(CompareMovesEvent {
+            result,
+            round,
+            score: game.score,
+        });
+
+        if let Some(match_result) = game.result {
+            emit!(MatchFinishedEvent {
+                id: game.id,
+                result: match_result,
+                winner: game.winner,
+                score: game.score,
+            });
+
+            settle_stakes(
+                &ctx.accounts.rps_game,
+                &ctx.accounts.vault,
+                &ctx.accounts.player_a,
+                &ctx.accounts.player_b,
+                &ctx.accounts.treasury,
+            )?;
+            advance_bracket(
+                &ctx.accounts.rps_game,
+                ctx.accounts.tournament.as_mut(),
+                ctx.accounts.next_game.as_mut(),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn init_check_moves_comp_def(ctx: Context<InitCheckMovesCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Ends a match whose move deadline has passed, awarding it to the player who moved.
+    pub fn claim_timeout(ctx: Context<ClaimTimeout>, computation_offset: u64) -> Result<()> {
+        let game = &ctx.accounts.rps_game;
+        require!(
+            ctx.accounts.payer.key() == game.player_a || ctx.accounts.payer.key() == game.player_b,
+            ErrorCode::NotAuthorized
+        );
+        game.require_state(GameState::AwaitingMoves)?;
+        require!(
+            Clock::get()?.unix_timestamp > game.move_deadline,
+            ErrorCode::MoveDeadlineNotReached
+        );
+        let (tournament, next_game) =
+            bracket_accounts(game, &ctx.accounts.tournament, &ctx.accounts.next_game)?;
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(game.nonce)
+            .account(game.key(), 8, 32 * 2)
+            .build();
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![CheckMovesCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &settlement_accounts(
+                    &ctx.accounts.rps_game,
+                    ctx.accounts.vault.key(),
+                    tournament,
+                    next_game,
+                ),
+            )?],
+            1,
+            0,
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "check_moves")]
+    pub fn check_moves_callback(
+        ctx: Context<CheckMovesCallback>,
+        output: SignedComputationOutputs<CheckMovesOutput>,
+    ) -> Result<()> {
+        let (player_a_moved, player_b_moved) = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CheckMovesOutput {
+                field_0:
+                    CheckMovesOutputStruct0 {
+                        field_0: player_a_moved,
+                        field_1: player_b_moved,
+                    },
+            }) => (player_a_moved, player_b_moved),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let game = &mut ctx.accounts.rps_game;
+        // A move may have completed the round while the check was queued
+        if game.state != GameState::AwaitingMoves {
+            return Ok(());
+        }
+
+        // If both players moved the round can still be compared, so the match carries on
+        match (player_a_moved, player_b_moved) {
+            (true, false) => game.resolve(RpsOutcome::PlayerAWins),
+            (false, true) => game.resolve(RpsOutcome::PlayerBWins),
+            // A bracket game needs a winner, so the higher seed, player A, goes through
+            (false, false) if game.tournament.is_some() => game.resolve(RpsOutcome::PlayerAWins),
+            (false, false) => game.resolve(RpsOutcome::InvalidMove),
+            (true, true) => {}
+        }
+
+        emit!(TimeoutClaimedEvent {
+            id: game.id,
+            player_a_moved,
+            player_b_moved,
+            winner: game.winner,
+        });
+
+        if game.result.is_some() {
+            settle_stakes(
+                &ctx.accounts.rps_game,
+                &ctx.accounts.vault,
+                &ctx.accounts.player_a,
+                &ctx.accounts.player_b,
+                &ctx.accounts.treasury,
+            )?;
+            advance_bracket(
+                &ctx.accounts.rps_game,
+                ctx.accounts.tournament.as_mut(),
+                ctx.accounts.next_game.as_mut(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Moves a player's stake into the game vault.
+fn deposit_stake<'info>(
+    player: &Signer<'info>,
+    vault: &Account<'info, GameVault>,
+    system_program: &Program<'info, System>,
+    stake: u64,
+) -> Result<()> {
+    if stake == 0 {
+        return Ok(());
+    }
+
+    system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            system_program::Transfer {
+                from: player.to_account_info(),
+                to: vault.to_account_info(),
+            },
+        ),
+        stake,
+    )
+}
+
+/// Checks the match length and move timeout a game or tournament is created with.
+fn check_match_terms(best_of: u8, move_timeout: i64) -> Result<()> {
+    require!(
+        best_of % 2 == 1 && best_of <= MAX_BEST_OF,
+        ErrorCode::InvalidBestOf
+    );
+    require!(move_timeout > 0, ErrorCode::InvalidMoveTimeout);
+    Ok(())
+}
+
+/// Accounts the round and timeout callbacks need to pay out a game that ends and, for a
+/// bracket game, advance its winner. Bracket accounts a game doesn't have are passed as
+/// the program id, which Anchor reads as an absent optional account.
+fn settlement_accounts(
+    game: &Account<RPSGame>,
+    vault: Pubkey,
+    tournament: Option<Pubkey>,
+    next_game: Option<Pubkey>,
+) -> Vec<CallbackAccount> {
+    let mut accounts = [
+        game.key(),
+        vault,
+        game.player_a,
+        game.player_b,
+        game.treasury,
+    ]
+    .map(|pubkey| CallbackAccount {
+        pubkey,
+        is_writable: true,
+    })
+    .to_vec();
+    accounts.extend([tournament, next_game].map(|account| CallbackAccount {
+        pubkey: account.unwrap_or(crate::ID),
+        is_writable: account.is_some(),
+    }));
+    accounts
+}
+
+/// Checks the bracket accounts passed in for a game and returns the keys its callback needs.
+///
+/// Tournament games must come with their running tournament and, unless they are the
+/// final, the game their winner advances to. Other games take neither.
+fn bracket_accounts(
+    game: &RPSGame,
+    tournament: &Option<Box<Account<Tournament>>>,
+    next_game: &Option<Box<Account<RPSGame>>>,
+) -> Result<(Option<Pubkey>, Option<Pubkey>)> {
+    let Some(tournament_key) = game.tournament else {
+        return Ok((None, None));
+    };
+
+    let tournament = tournament
+        .as_ref()
+        .filter(|tournament| tournament.key() == tournament_key)
+        .ok_or(ErrorCode::InvalidBracketGame)?;
+    require!(
+        tournament.state == TournamentState::InProgress,
+        ErrorCode::InvalidTournamentState
+    );
+    if game.match_index == 1 {
+        return Ok((Some(tournament_key), None));
+    }
+
+    let next_game = next_game
+        .as_ref()
+        .filter(|next_game| game.feeds_into(next_game))
+        .ok_or(ErrorCode::InvalidBracketGame)?;
+    Ok((Some(tournament_key), Some(next_game.key())))
+}
+
+/// Moves the winner of a finished bracket game into its next match, or ends the
+/// tournament when the final is decided.
+fn advance_bracket(
+    game: &RPSGame,
+    tournament: Option<&mut Box<Account<Tournament>>>,
+    next_game: Option<&mut Box<Account<RPSGame>>>,
+) -> Result<()> {
+    let Some(tournament_key) = game.tournament else {
+        return Ok(());
+    };
+    let tournament = tournament
+        .filter(|tournament| tournament.key() == tournament_key)
+        .ok_or(ErrorCode::InvalidBracketGame)?;
+    // The tournament may have been cancelled while this game's result was queued
+    if tournament.state != TournamentState::InProgress {
+        return Ok(());
+    }
+    // Level bracket games are replayed, so a finished one always has a winner
+    let winner = game.winner.ok_or(ErrorCode::InvalidBracketGame)?;
+
+    if game.match_index == 1 {
+        let runner_up = if winner == game.player_a {
+            game.player_b
+        } else {
+            game.player_a
+        };
+        tournament.champion = Some(winner);
+        tournament.runner_up = Some(runner_up);
+        tournament.state = TournamentState::Finished;
+
+        emit!(TournamentFinishedEvent {
+            tournament: tournament_key,
+            champion: winner,
+            runner_up,
+        });
+        return Ok(());
+    }
+
+    let next_game = next_game
+        .filter(|next_game| game.feeds_into(next_game))
+        .ok_or(ErrorCode::InvalidBracketGame)?;
+    // The winner of the even-numbered feeder match plays as player A
+    if game.match_index % 2 == 0 {
+        next_game.player_a = winner;
+    } else {
+        next_game.player_b = winner;
+    }
+    if next_game.player_a != Pubkey::default() && next_game.player_b != Pubkey::default() {
+        next_game.state = GameState::AwaitingMoves;
+        next_game.start_move_window()?;
+    }
+
+    emit!(BracketAdvancedEvent {
+        tournament: tournament_key,
+        player: winner,
+        match_index: next_game.match_index,
+    });
+    Ok(())
+}
+
+/// Fails unless `account` holds enough lamports to stay rent-exempt on its own.
+fn require_rent_exempt(account: &UncheckedAccount) -> Result<()> {
+    require!(
+        Rent::get()?.is_exempt(account.lamports(), account.data_len()),
+        ErrorCode::TreasuryNotRentExempt
+    );
+    Ok(())
+}
+
+/// Pays out the stakes of a resolved game.
+///
+/// When both stakes are in, the winner takes the pot less the protocol fee. Ties, invalid
+/// games and forfeits against a player who never staked return each stake to its owner.
+fn settle_stakes<'info>(
+    game: &RPSGame,
+    vault: &Account<'info, GameVault>,
+    player_a: &SystemAccount<'info>,
+    player_b: &SystemAccount<'info>,
+    treasury: &UncheckedAccount<'info>,
+) -> Result<()> {
+    if game.stake == 0 {
+        return Ok(());
+    }
+
+    let refund = |slot: usize| {
+        if game.stakes_deposited[slot] {
+            game.stake
+        } else {
+            0
+        }
+    };
+    let mut payouts = [refund(0), refund(1)];
+    let mut fee = 0;
+
+    let winner_slot = match game.result {
+        Some(RpsOutcome::PlayerAWins) => Some(0),
+        Some(RpsOutcome::PlayerBWins) => Some(1),
+        _ => None,
+    };
+    if let (Some(winner), [true, true]) = (winner_slot, game.stakes_deposited) {
+        let pot = game.stake.checked_mul(2).ok_or(ErrorCode::Overflow)?;
+        fee = (pot as u128 * game.fee_bps as u128 / BASIS_POINTS as u128) as u64;
+        payouts = [0, 0];
+        payouts[winner] = pot - fee;
+    }
+
+    vault.sub_lamports(payouts[0] + payouts[1] + fee)?;
+    player_a.add_lamports(payouts[0])?;
+    player_b.add_lamports(payouts[1])?;
+    if fee > 0 {
+        treasury.add_lamports(fee)?;
+    }
+
+    emit!(StakesSettledEvent {
+        id: game.id,
+        payouts,
+        fee,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProtocolConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    /// CHECK: treasury, only receives fees and is checked to be rent-exempt
+    pub treasury: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::NotAuthorized,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    /// CHECK: treasury, only receives fees and is checked to be rent-exempt
+    pub treasury: UncheckedAccount<'info>,
+}
+
+#[queue_computation_accounts("init_game", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, id: u64)]
+pub struct InitGame<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_GAME)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        mut,
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(init,
+        payer = payer,
+        space = 8 + RPSGame::INIT_SPACE,
+        seeds = [b"rps_game", id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub rps_game: Account<'info, RPSGame>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + GameVault::INIT_SPACE,
+        seeds = [b"game_vault", rps_game.key().as_ref()],
+        bump,
+    )]
+    pub vault: Account<'info, GameVault>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[queue_computation_accounts("init_game", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, id: u64)]
+pub struct CreateGame<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_GAME)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        mut,
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(init,
+        payer = payer,
+        space = 8 + RPSGame::INIT_SPACE,
+        seeds = [b"rps_game", payer.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub rps_game: Account<'info, RPSGame>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + GameVault::INIT_SPACE,
+        seeds = [b"game_vault", rps_game.key().as_ref()],
+        bump,
+    )]
+    pub vault: Account<'info, GameVault>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Lobby::INIT_SPACE,
+        seeds = [b"lobby"],
+        bump,
+    )]
+    pub lobby: Account<'info, Lobby>,
+}
+
+#[derive(Accounts)]
+pub struct JoinGame<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+    #[account(mut)]
+    pub rps_game: Account<'info, RPSGame>,
+    #[account(
+        mut,
+        seeds = [b"game_vault", rps_game.key().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, GameVault>,
+    #[account(mut, seeds = [b"lobby"], bump)]
+    pub lobby: Account<'info, Lobby>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelGame<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    #[account(
+        mut,
+        constraint = rps_game.player_a == creator.key() @ ErrorCode::NotAuthorized,
+        close = creator,
+    )]
+    pub rps_game: Account<'info, RPSGame>,
+    #[account(
+        mut,
+        seeds = [b"game_vault", rps_game.key().as_ref()],
+        bump = vault.bump,
+        close = creator,
+    )]
+    pub vault: Account<'info, GameVault>,
+    #[account(mut, seeds = [b"lobby"], bump)]
+    pub lobby: Account<'info, Lobby>,
+}
+
+#[derive(Accounts)]
+pub struct PruneLobby<'info> {
+    #[account(mut, seeds = [b"lobby"], bump)]
+    pub lobby: Account<'info, Lobby>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateTournament<'info> {
+    #[account(mut)]
+    pub organizer: Signer<'info>,
+    #[account(
+        init,
+        payer = organizer,
+        space = 8 + Tournament::INIT_SPACE,
+        seeds = [b"tournament", organizer.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub tournament: Box<Account<'info, Tournament>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Register<'info> {
+    #[account(mut)]
+    pub entrant: Signer<'info>,
+    #[account(mut)]
+    pub tournament: Box<Account<'info, Tournament>>,
+    pub system_program: Program<'info, System>,
+}
 
-        emit!(CompareMovesEvent {
-            result: result_str.to_string(),
-        });
-        Ok(())
-    }
+#[derive(Accounts)]
+pub struct CancelTournament<'info> {
+    pub organizer: Signer<'info>,
+    #[account(
+        mut,
+        has_one = organizer @ ErrorCode::NotAuthorized,
+    )]
+    pub tournament: Box<Account<'info, Tournament>>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawEntry<'info> {
+    #[account(mut)]
+    pub entrant: Signer<'info>,
+    #[account(mut)]
+    pub tournament: Box<Account<'info, Tournament>>,
 }
 
 #[queue_computation_accounts("init_game", payer)]
 #[derive(Accounts)]
-#[instruction(computation_offset: u64, id: u64)]
-pub struct InitGame<'info> {
+#[instruction(computation_offset: u64, match_index: u8)]
+pub struct CreateBracketGame<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(
@@ -261,13 +1343,35 @@ pub struct InitGame<'info> {
     pub clock_account: Account<'info, ClockAccount>,
     pub system_program: Program<'info, System>,
     pub arcium_program: Program<'info, Arcium>,
+    #[account(mut)]
+    pub tournament: Box<Account<'info, Tournament>>,
     #[account(init,
         payer = payer,
         space = 8 + RPSGame::INIT_SPACE,
-        seeds = [b"rps_game", id.to_le_bytes().as_ref()],
+        seeds = [b"rps_game", tournament.key().as_ref(), match_index.to_le_bytes().as_ref()],
         bump,
     )]
     pub rps_game: Account<'info, RPSGame>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + GameVault::INIT_SPACE,
+        seeds = [b"game_vault", rps_game.key().as_ref()],
+        bump,
+    )]
+    pub vault: Account<'info, GameVault>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct DistributePrizes<'info> {
+    #[account(mut)]
+    pub tournament: Box<Account<'info, Tournament>>,
+    #[account(mut, address = tournament.champion.unwrap_or_default())]
+    pub champion: SystemAccount<'info>,
+    #[account(mut, address = tournament.runner_up.unwrap_or_default())]
+    pub runner_up: SystemAccount<'info>,
 }
 
 #[callback_accounts("init_game")]
@@ -379,6 +1483,12 @@ pub struct PlayerMove<'info> {
     pub arcium_program: Program<'info, Arcium>,
     #[account(mut)]
     pub rps_game: Account<'info, RPSGame>,
+    #[account(
+        mut,
+        seeds = [b"game_vault", rps_game.key().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, GameVault>,
 }
 
 #[callback_accounts("player_move")]
@@ -490,6 +1600,16 @@ pub struct CompareMoves<'info> {
     pub arcium_program: Program<'info, Arcium>,
     #[account(mut)]
     pub rps_game: Account<'info, RPSGame>,
+    #[account(
+        mut,
+        seeds = [b"game_vault", rps_game.key().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, GameVault>,
+    /// Tournament the game belongs to, for bracket games only
+    pub tournament: Option<Box<Account<'info, Tournament>>>,
+    /// Game the winner advances to, for bracket games before the final
+    pub next_game: Option<Box<Account<'info, RPSGame>>>,
 }
 
 #[callback_accounts("compare_moves")]
@@ -513,6 +1633,25 @@ pub struct CompareMovesCallback<'info> {
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar, checked by the account constraint
     pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub rps_game: Account<'info, RPSGame>,
+    #[account(
+        mut,
+        seeds = [b"game_vault", rps_game.key().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, GameVault>,
+    #[account(mut, address = rps_game.player_a)]
+    pub player_a: SystemAccount<'info>,
+    #[account(mut, address = rps_game.player_b)]
+    pub player_b: SystemAccount<'info>,
+    #[account(mut, address = rps_game.treasury)]
+    /// CHECK: treasury, checked against the address recorded in the game
+    pub treasury: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub tournament: Option<Box<Account<'info, Tournament>>>,
+    #[account(mut)]
+    pub next_game: Option<Box<Account<'info, RPSGame>>>,
 }
 
 #[init_computation_definition_accounts("compare_moves", payer)]
@@ -539,6 +1678,144 @@ pub struct InitCompareMovesCompDef<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[queue_computation_accounts("check_moves", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ClaimTimeout<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_MOVES)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        mut,
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(mut)]
+    pub rps_game: Account<'info, RPSGame>,
+    #[account(
+        mut,
+        seeds = [b"game_vault", rps_game.key().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, GameVault>,
+    /// Tournament the game belongs to, for bracket games only
+    pub tournament: Option<Box<Account<'info, Tournament>>>,
+    /// Game the winner advances to, for bracket games before the final
+    pub next_game: Option<Box<Account<'info, RPSGame>>>,
+}
+
+#[callback_accounts("check_moves")]
+#[derive(Accounts)]
+pub struct CheckMovesCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_MOVES)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub rps_game: Account<'info, RPSGame>,
+    #[account(
+        mut,
+        seeds = [b"game_vault", rps_game.key().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, GameVault>,
+    #[account(mut, address = rps_game.player_a)]
+    pub player_a: SystemAccount<'info>,
+    #[account(mut, address = rps_game.player_b)]
+    pub player_b: SystemAccount<'info>,
+    #[account(mut, address = rps_game.treasury)]
+    /// CHECK: treasury, checked against the address recorded in the game
+    pub treasury: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub tournament: Option<Box<Account<'info, Tournament>>>,
+    #[account(mut)]
+    pub next_game: Option<Box<Account<'info, RPSGame>>>,
+}
+
+#[init_computation_definition_accounts("check_moves", payer)]
+#[derive(Accounts)]
+pub struct InitCheckMovesCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table, checked by arcium program.
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program is the Address Lookup Table program.
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct RPSGame {
@@ -547,11 +1824,353 @@ pub struct RPSGame {
     pub player_b: Pubkey,
     pub nonce: u128,
     pub id: u64,
+    /// Lamports each player puts up, escrowed in the game vault
+    pub stake: u64,
+    /// Whether player A and player B have deposited their stake
+    pub stakes_deposited: [bool; 2],
+    /// Protocol fee taken from the pot of a decided game, fixed when the game is created
+    pub fee_bps: u16,
+    /// Account the protocol fee is paid to
+    pub treasury: Pubkey,
+    /// Number of scoring rounds in the match, always odd
+    pub best_of: u8,
+    /// Rounds compared so far, including ties and invalid rounds
+    pub rounds_played: u8,
+    /// Rounds won by player A and player B
+    pub score: [u8; 2],
+    /// Seconds each player has to submit their move, per round
+    pub move_timeout: i64,
+    /// Unix timestamp after which moves are closed for the current round and a timeout can be claimed
+    pub move_deadline: i64,
+    /// Where the game is in its lifecycle
+    pub state: GameState,
+    /// Whether player A and player B have a valid move recorded for the current round
+    pub moves_submitted: [bool; 2],
+    /// Final match result, set when the game is resolved
+    pub result: Option<RpsOutcome>,
+    /// Match winner, `None` until the game is resolved, or if it ended without one
+    pub winner: Option<Pubkey>,
+    /// Tournament this game is part of, if it is a bracket game
+    pub tournament: Option<Pubkey>,
+    /// Position of a bracket game in its tournament, 1 being the final
+    pub match_index: u8,
+    /// Revealed result of every round, in order
+    #[max_len(MAX_ROUNDS)]
+    pub round_results: Vec<RpsOutcome>,
+}
+
+impl RPSGame {
+    /// Round wins needed to take the match.
+    pub fn wins_needed(&self) -> u8 {
+        self.best_of / 2 + 1
+    }
+
+    fn setup(
+        &mut self,
+        id: u64,
+        player_a: Pubkey,
+        stake: u64,
+        best_of: u8,
+        move_timeout: i64,
+        config: &ProtocolConfig,
+    ) -> Result<()> {
+        check_match_terms(best_of, move_timeout)?;
+
+        self.id = id;
+        self.player_a = player_a;
+        self.stake = stake;
+        self.fee_bps = config.fee_bps;
+        self.treasury = config.treasury;
+        self.best_of = best_of;
+        self.move_timeout = move_timeout;
+        Ok(())
+    }
+
+    /// Gives both players `move_timeout` seconds from now to move.
+    fn start_move_window(&mut self) -> Result<()> {
+        self.move_deadline = Clock::get()?
+            .unix_timestamp
+            .checked_add(self.move_timeout)
+            .ok_or(ErrorCode::InvalidMoveTimeout)?;
+        Ok(())
+    }
+
+    /// Player slot of `player`: 0 for player A, 1 for player B.
+    fn slot_of(&self, player: &Pubkey) -> Result<u8> {
+        if *player == self.player_a {
+            Ok(0)
+        } else if *player == self.player_b {
+            Ok(1)
+        } else {
+            Err(ErrorCode::NotAuthorized.into())
+        }
+    }
+
+    fn require_state(&self, state: GameState) -> Result<()> {
+        require!(self.state != GameState::Resolved, ErrorCode::MatchFinished);
+        require!(self.state == state, ErrorCode::InvalidGameState);
+        Ok(())
+    }
+
+    /// Ends the match. `Tie` means it ended level, `InvalidMove` that neither player moved.
+    fn resolve(&mut self, result: RpsOutcome) {
+        self.state = GameState::Resolved;
+        self.result = Some(result);
+        self.winner = match result {
+            RpsOutcome::PlayerAWins => Some(self.player_a),
+            RpsOutcome::PlayerBWins => Some(self.player_b),
+            RpsOutcome::Tie | RpsOutcome::InvalidMove => None,
+        };
+    }
+
+    fn record_round(&mut self, result: RpsOutcome) {
+        self.rounds_played += 1;
+        self.round_results.push(result);
+
+        match result {
+            RpsOutcome::PlayerAWins => self.score[0] += 1,
+            RpsOutcome::PlayerBWins => self.score[1] += 1,
+            RpsOutcome::Tie | RpsOutcome::InvalidMove => {}
+        }
+
+        if self.score[0] >= self.wins_needed() {
+            self.resolve(RpsOutcome::PlayerAWins);
+        } else if self.score[1] >= self.wins_needed() {
+            self.resolve(RpsOutcome::PlayerBWins);
+        } else if self.round_results.len() >= MAX_ROUNDS {
+            match self.score[0].cmp(&self.score[1]) {
+                std::cmp::Ordering::Greater => self.resolve(RpsOutcome::PlayerAWins),
+                std::cmp::Ordering::Less => self.resolve(RpsOutcome::PlayerBWins),
+                // A bracket game needs a winner, so a level match is replayed from scratch
+                std::cmp::Ordering::Equal if self.tournament.is_some() => self.replay(),
+                std::cmp::Ordering::Equal => self.resolve(RpsOutcome::Tie),
+            }
+        }
+    }
+
+    /// Whether the winner of this bracket game goes on to play `next_game`.
+    fn feeds_into(&self, next_game: &RPSGame) -> bool {
+        self.tournament.is_some()
+            && next_game.tournament == self.tournament
+            && next_game.match_index == self.match_index / 2
+    }
+
+    /// Clears the score and round history so the match starts over.
+    fn replay(&mut self) {
+        self.rounds_played = 0;
+        self.score = [0, 0];
+        self.round_results.clear();
+    }
+}
+
+/// Protocol fee settings applied to new staked games.
+#[account]
+#[derive(InitSpace)]
+pub struct ProtocolConfig {
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub treasury: Pubkey,
+    pub fee_bps: u16,
+}
+
+/// Escrow holding both players' stakes until the game is settled.
+#[account]
+#[derive(InitSpace)]
+pub struct GameVault {
+    pub bump: u8,
+}
+
+/// Index of lobby games waiting for a second player.
+#[account]
+#[derive(InitSpace)]
+pub struct Lobby {
+    #[max_len(MAX_OPEN_GAMES)]
+    pub open_games: Vec<LobbyListing>,
+}
+
+impl Lobby {
+    /// Lists `game` until `LOBBY_LISTING_SECONDS` from now, making room by pruning first.
+    fn list(&mut self, game: Pubkey, now: i64) -> Result<()> {
+        self.prune(now);
+        require!(self.open_games.len() < MAX_OPEN_GAMES, ErrorCode::LobbyFull);
+        self.open_games.push(LobbyListing {
+            game,
+            expires_at: now + LOBBY_LISTING_SECONDS,
+        });
+        Ok(())
+    }
+
+    /// Delists `game` for a player joining it, failing if its listing is gone or expired.
+    fn take(&mut self, game: &Pubkey, now: i64) -> Result<()> {
+        let listed = self
+            .open_games
+            .iter()
+            .any(|listing| listing.game == *game && listing.expires_at > now);
+        require!(listed, ErrorCode::ListingExpired);
+        self.remove(game);
+        Ok(())
+    }
+
+    fn remove(&mut self, game: &Pubkey) {
+        self.open_games.retain(|listing| listing.game != *game);
+    }
+
+    fn prune(&mut self, now: i64) {
+        self.open_games.retain(|listing| listing.expires_at > now);
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct LobbyListing {
+    pub game: Pubkey,
+    /// Unix timestamp after which the game can no longer be joined and can be pruned
+    pub expires_at: i64,
+}
+
+/// Single-elimination tournament whose entry fees are paid out as prizes.
+#[account]
+#[derive(InitSpace)]
+pub struct Tournament {
+    pub bump: u8,
+    pub organizer: Pubkey,
+    pub id: u64,
+    /// Number of entrants, a power of two
+    pub size: u8,
+    /// Lamports each entrant pays into the prize pool, held in this account
+    pub entry_fee: u64,
+    /// Match length of every bracket game
+    pub best_of: u8,
+    /// Move timeout of every bracket game
+    pub move_timeout: i64,
+    /// Share of the prize pool for the champion and the runner-up
+    pub prize_shares_bps: [u16; 2],
+    pub state: TournamentState,
+    /// Bracket games created so far
+    pub games_created: u8,
+    /// Registered entrants, in seeding order
+    #[max_len(MAX_ENTRANTS)]
+    pub entrants: Vec<Pubkey>,
+    /// Winner of the final
+    pub champion: Option<Pubkey>,
+    /// Loser of the final
+    pub runner_up: Option<Pubkey>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum GameState {
+    /// Lobby game waiting for a second player to join
+    Open,
+    /// Bracket game waiting for the winners of its feeder matches
+    AwaitingPlayers,
+    /// Waiting for one or both players to submit a move for the current round
+    AwaitingMoves,
+    /// Both moves are in and the round can be compared
+    ReadyToCompare,
+    /// The match is over and its result is final
+    Resolved,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum TournamentState {
+    /// Taking entrants until the bracket is full
+    Registration,
+    /// Full, with bracket games still to be created
+    Seeding,
+    /// Every bracket game exists and matches are being played
+    InProgress,
+    /// The final is decided and prizes can be paid out
+    Finished,
+    PrizesPaid,
+    /// Called off before the final was decided; entrants can withdraw their fees
+    Cancelled,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum RpsOutcome {
+    Tie,
+    PlayerAWins,
+    PlayerBWins,
+    InvalidMove,
+}
+
+impl RpsOutcome {
+    /// Maps the result code revealed by the `compare_moves` circuit.
+    fn from_code(code: u8) -> Result<Self> {
+        match code {
+            0 => Ok(RpsOutcome::Tie),
+            1 => Ok(RpsOutcome::PlayerAWins),
+            2 => Ok(RpsOutcome::PlayerBWins),
+            3 => Ok(RpsOutcome::InvalidMove),
+            _ => Err(ErrorCode::InvalidGameResult.into()),
+        }
+    }
 }
 
 #[event]
 pub struct CompareMovesEvent {
-    pub result: String,
+    pub result: RpsOutcome,
+    /// Round number this result belongs to, starting at 1
+    pub round: u8,
+    /// Running score of player A and player B
+    pub score: [u8; 2],
+}
+
+#[event]
+pub struct GameJoinedEvent {
+    pub id: u64,
+    pub player_a: Pubkey,
+    pub player_b: Pubkey,
+}
+
+#[event]
+pub struct MatchFinishedEvent {
+    pub id: u64,
+    pub result: RpsOutcome,
+    pub winner: Option<Pubkey>,
+    pub score: [u8; 2],
+}
+
+#[event]
+pub struct StakesSettledEvent {
+    pub id: u64,
+    /// Lamports paid to player A and player B
+    pub payouts: [u64; 2],
+    /// Protocol fee paid to the treasury
+    pub fee: u64,
+}
+
+#[event]
+pub struct TimeoutClaimedEvent {
+    pub id: u64,
+    pub player_a_moved: bool,
+    pub player_b_moved: bool,
+    /// Player awarded the match, `None` if both players had moved, or neither outside a
+    /// tournament
+    pub winner: Option<Pubkey>,
+}
+
+#[event]
+pub struct BracketAdvancedEvent {
+    pub tournament: Pubkey,
+    pub player: Pubkey,
+    /// Match the player advanced to
+    pub match_index: u8,
+}
+
+#[event]
+pub struct TournamentFinishedEvent {
+    pub tournament: Pubkey,
+    pub champion: Pubkey,
+    pub runner_up: Pubkey,
+}
+
+#[event]
+pub struct PrizesDistributedEvent {
+    pub tournament: Pubkey,
+    pub champion: Pubkey,
+    pub champion_prize: u64,
+    pub runner_up: Pubkey,
+    pub runner_up_prize: u64,
 }
 
 #[error_code]
@@ -562,4 +2181,44 @@ pub enum ErrorCode {
     NotAuthorized,
     #[msg("Cluster not set")]
     ClusterNotSet,
+    #[msg("Unexpected game result value from computation")]
+    InvalidGameResult,
+    #[msg("Matches must be best of an odd number of rounds, up to 7")]
+    InvalidBestOf,
+    #[msg("The match is already finished")]
+    MatchFinished,
+    #[msg("The game is not in the right state for this instruction")]
+    InvalidGameState,
+    #[msg("This player has already moved this round")]
+    MoveAlreadySubmitted,
+    #[msg("The lobby has no room for more open games")]
+    LobbyFull,
+    #[msg("Cannot join your own game")]
+    CannotJoinOwnGame,
+    #[msg("Protocol fee exceeds the maximum")]
+    InvalidProtocolFee,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Move timeout must be positive")]
+    InvalidMoveTimeout,
+    #[msg("The move deadline has passed")]
+    MoveDeadlinePassed,
+    #[msg("The move deadline has not passed yet")]
+    MoveDeadlineNotReached,
+    #[msg("Tournaments must have a power of two entrants, from 2 to 16")]
+    InvalidTournamentSize,
+    #[msg("Prize shares must add up to 100% and favour the champion")]
+    InvalidPrizeShares,
+    #[msg("The tournament is not in the right state for this instruction")]
+    InvalidTournamentState,
+    #[msg("Already registered for this tournament")]
+    AlreadyRegistered,
+    #[msg("Missing or mismatched bracket game")]
+    InvalidBracketGame,
+    #[msg("This lobby game is no longer listed; its creator can cancel it")]
+    ListingExpired,
+    #[msg("The treasury must be rent-exempt to receive protocol fees")]
+    TreasuryNotRentExempt,
+    #[msg("Not registered for this tournament")]
+    NotRegistered,
 }
@@ -58,6 +58,10 @@ describe("RockPaperScissors", () => {
   const arciumEnv = getArciumEnv();
   const clusterAccount = getClusterAccAddress(arciumEnv.arciumClusterOffset);
 
+  // Receives the protocol fee from staked games
+  const treasury = Keypair.generate();
+  const protocolFeeBps = 200;
+
   // Combined test suite for Rock Paper Scissors game
   it("Tests the complete Rock Paper Scissors game flow", async () => {
     const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
@@ -94,6 +98,29 @@ describe("RockPaperScissors", () => {
       compareMovesSig
     );
 
+    // The treasury must be rent-exempt, or small fees could never be credited to it
+    try {
+      await program.methods
+        .initializeConfig(protocolFeeBps)
+        .accounts({
+          authority: owner.publicKey,
+          treasury: treasury.publicKey,
+        })
+        .signers([owner])
+        .rpc({ commitment: "confirmed" });
+      expect.fail("Configured an unfunded treasury");
+    } catch (error) {
+      expect(error.message).to.include("TreasuryNotRentExempt");
+    }
+    await airdrop(treasury.publicKey);
+
+    // The protocol config must exist before any game is created
+    await program.methods
+      .initializeConfig(protocolFeeBps)
+      .accounts({ authority: owner.publicKey, treasury: treasury.publicKey })
+      .signers([owner])
+      .rpc({ commitment: "confirmed" });
+
     // Step 2: Play a complete game with two players
     console.log("\n--- Playing a complete game with two players ---");
 
@@ -126,7 +153,10 @@ describe("RockPaperScissors", () => {
         initComputationOffset,
         new anchor.BN(gameId),
         playerA.publicKey,
-        playerB.publicKey
+        playerB.publicKey,
+        new anchor.BN(0),
+        1,
+        new anchor.BN(3600)
       )
       .accounts({
         computationAccount: getComputationAccAddress(
@@ -179,10 +209,9 @@ describe("RockPaperScissors", () => {
 
     // Player A makes a move (Rock)
     const playerAMove = 0; // Rock
-    const playerAId = 0;
     const playerANonce = randomBytes(16);
     const playerACiphertext = playerACipher.encrypt(
-      [BigInt(playerAId), BigInt(playerAMove)],
+      [BigInt(playerAMove)],
       playerANonce
     );
 
@@ -193,7 +222,6 @@ describe("RockPaperScissors", () => {
       .playerMove(
         playerAMoveComputationOffset,
         Array.from(playerACiphertext[0]),
-        Array.from(playerACiphertext[1]),
         Array.from(playerAPublicKey),
         new anchor.BN(deserializeLE(playerANonce).toString())
       )
@@ -255,10 +283,9 @@ describe("RockPaperScissors", () => {
 
     // Player B makes a move (Scissors)
     const playerBMove = 2; // Scissors
-    const playerBId = 1;
     const playerBNonce = randomBytes(16);
     const playerBCiphertext = playerBCipher.encrypt(
-      [BigInt(playerBId), BigInt(playerBMove)],
+      [BigInt(playerBMove)],
       playerBNonce
     );
 
@@ -269,7 +296,6 @@ describe("RockPaperScissors", () => {
       .playerMove(
         playerBMoveComputationOffset,
         Array.from(playerBCiphertext[0]),
-        Array.from(playerBCiphertext[1]),
         Array.from(playerBPublicKey),
         new anchor.BN(deserializeLE(playerBNonce).toString())
       )
@@ -327,7 +353,7 @@ describe("RockPaperScissors", () => {
           arciumEnv.arciumClusterOffset,
           compareComputationOffset
         ),
-        payer: owner.publicKey,
+        payer: playerA.publicKey,
         mxeAccount: getMXEAccAddress(program.programId),
         mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
         executingPool: getExecutingPoolAccAddress(
@@ -345,7 +371,10 @@ describe("RockPaperScissors", () => {
           ],
           program.programId
         )[0],
+        tournament: null,
+        nextGame: null,
       })
+      .signers([playerA])
       .rpc({
         skipPreflight: true,
         commitment: "confirmed",
@@ -360,10 +389,10 @@ describe("RockPaperScissors", () => {
     console.log("Finalize signature:", finalizeSig);
 
     const gameEvent = await gameEventPromise;
-    console.log(`Game result: ${gameEvent.result}`);
+    console.log("Game result:", gameEvent.result);
 
     // Verify the result (Rock beats Scissors, so Player A wins)
-    expect(gameEvent.result).to.equal("Player A Wins");
+    expect(gameEvent.result).to.deep.equal({ playerAWins: {} });
 
     // Step 3: Test unauthorized player trying to make a move
     console.log("\n--- Testing unauthorized player ---");
@@ -392,7 +421,10 @@ describe("RockPaperScissors", () => {
         initComputationOffset2,
         gameId2,
         playerA.publicKey,
-        playerB.publicKey
+        playerB.publicKey,
+        new anchor.BN(0),
+        1,
+        new anchor.BN(3600)
       )
       .accounts({
         computationAccount: getComputationAccAddress(
@@ -447,7 +479,7 @@ describe("RockPaperScissors", () => {
     const unauthorizedMove = 1; // Paper
     const unauthorizedNonce = randomBytes(16);
     const unauthorizedCiphertext = unauthorizedCipher.encrypt(
-      [BigInt(0), BigInt(unauthorizedMove)],
+      [BigInt(unauthorizedMove)],
       unauthorizedNonce
     );
 
@@ -462,7 +494,6 @@ describe("RockPaperScissors", () => {
         .playerMove(
           unauthorizedMoveComputationOffset,
           Array.from(unauthorizedCiphertext[0]),
-          Array.from(unauthorizedCiphertext[1]),
           Array.from(unauthorizedPublicKey),
           new anchor.BN(deserializeLE(unauthorizedNonce).toString())
         )
@@ -545,7 +576,10 @@ describe("RockPaperScissors", () => {
           initComputationOffset3,
           scenarioGameId,
           playerA.publicKey,
-          playerB.publicKey
+          playerB.publicKey,
+          new anchor.BN(0),
+          1,
+          new anchor.BN(3600)
         )
         .accounts({
           computationAccount: getComputationAccAddress(
@@ -584,7 +618,7 @@ describe("RockPaperScissors", () => {
       // Player A makes a move
       const playerAMoveNonce = randomBytes(16);
       const playerAMoveCiphertext = playerACipher.encrypt(
-        [BigInt(0), BigInt(game.player)],
+        [BigInt(game.player)],
         playerAMoveNonce
       );
 
@@ -595,7 +629,6 @@ describe("RockPaperScissors", () => {
         .playerMove(
           playerAMoveComputationOffset,
           Array.from(playerAMoveCiphertext[0]),
-          Array.from(playerAMoveCiphertext[1]),
           Array.from(playerAPublicKey),
           new anchor.BN(deserializeLE(playerAMoveNonce).toString())
         )
@@ -643,7 +676,7 @@ describe("RockPaperScissors", () => {
       // Player B makes a move
       const playerBMoveNonce = randomBytes(16);
       const playerBMoveCiphertext = playerBCipher.encrypt(
-        [BigInt(1), BigInt(game.house)],
+        [BigInt(game.house)],
         playerBMoveNonce
       );
 
@@ -654,7 +687,6 @@ describe("RockPaperScissors", () => {
         .playerMove(
           playerBMoveComputationOffset,
           Array.from(playerBMoveCiphertext[0]),
-          Array.from(playerBMoveCiphertext[1]),
           Array.from(playerBPublicKey),
           new anchor.BN(deserializeLE(playerBMoveNonce).toString())
         )
@@ -712,7 +744,7 @@ describe("RockPaperScissors", () => {
             arciumEnv.arciumClusterOffset,
             compareComputationOffset
           ),
-          payer: owner.publicKey,
+          payer: playerA.publicKey,
           mxeAccount: getMXEAccAddress(program.programId),
           mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
           executingPool: getExecutingPoolAccAddress(
@@ -730,7 +762,10 @@ describe("RockPaperScissors", () => {
             ],
             program.programId
           )[0],
+          tournament: null,
+          nextGame: null,
         })
+        .signers([playerA])
         .rpc({
           skipPreflight: true,
           commitment: "confirmed",
@@ -747,23 +782,23 @@ describe("RockPaperScissors", () => {
       console.log("Finalize signature:", finalizeSig);
 
       const gameEvent = await gameEventPromise;
-      console.log(`Game result: ${gameEvent.result}`);
+      console.log("Game result:", gameEvent.result);
 
       // Verify the result based on the expected outcome
-      let expectedResult: string;
+      let expectedResult: object;
       if (game.player === game.house) {
-        expectedResult = "Tie";
+        expectedResult = { tie: {} };
       } else if (
         (game.player === 0 && game.house === 2) || // Rock beats Scissors
         (game.player === 1 && game.house === 0) || // Paper beats Rock
         (game.player === 2 && game.house === 1) // Scissors beats Paper
       ) {
-        expectedResult = "Player A Wins";
+        expectedResult = { playerAWins: {} };
       } else {
-        expectedResult = "Player B Wins";
+        expectedResult = { playerBWins: {} };
       }
 
-      expect(gameEvent.result).to.equal(expectedResult);
+      expect(gameEvent.result).to.deep.equal(expectedResult);
     }
 
     // Step 5: Test invalid move scenario
@@ -782,7 +817,10 @@ describe("RockPaperScissors", () => {
         initComputationOffset4,
         gameId3,
         playerA.publicKey,
-        playerB.publicKey
+        playerB.publicKey,
+        new anchor.BN(0),
+        1,
+        new anchor.BN(3600)
       )
       .accounts({
         computationAccount: getComputationAccAddress(
@@ -823,10 +861,9 @@ describe("RockPaperScissors", () => {
 
     // Player A makes a valid move (Rock = 0)
     const playerAValidMove = 0;
-    const playerAId3 = 0;
     const playerANonce3 = randomBytes(16);
     const playerACiphertext3 = playerACipher.encrypt(
-      [BigInt(playerAId3), BigInt(playerAValidMove)],
+      [BigInt(playerAValidMove)],
       playerANonce3
     );
 
@@ -837,7 +874,6 @@ describe("RockPaperScissors", () => {
       .playerMove(
         playerAMoveComputationOffset3,
         Array.from(playerACiphertext3[0]),
-        Array.from(playerACiphertext3[1]),
         Array.from(playerAPublicKey),
         new anchor.BN(deserializeLE(playerANonce3).toString())
       )
@@ -881,10 +917,9 @@ describe("RockPaperScissors", () => {
 
     // Player B makes an invalid move (4)
     const playerBInvalidMove = 4;
-    const playerBId3 = 1;
     const playerBNonce3 = randomBytes(16);
     const playerBCiphertext3 = playerBCipher.encrypt(
-      [BigInt(playerBId3), BigInt(playerBInvalidMove)],
+      [BigInt(playerBInvalidMove)],
       playerBNonce3
     );
 
@@ -895,7 +930,6 @@ describe("RockPaperScissors", () => {
       .playerMove(
         playerBMoveComputationOffset3,
         Array.from(playerBCiphertext3[0]),
-        Array.from(playerBCiphertext3[1]),
         Array.from(playerBPublicKey),
         new anchor.BN(deserializeLE(playerBNonce3).toString())
       )
@@ -937,20 +971,177 @@ describe("RockPaperScissors", () => {
     );
     console.log("Player B move finalize signature:", playerBMoveFinalizeSig3);
 
-    // Compare moves
-    const gameEventPromise3 = awaitEvent("compareMovesEvent");
-
-    const compareComputationOffset3 = new anchor.BN(randomBytes(8), "hex");
+    // The invalid move was never recorded, so only player A's slot is filled
+    const gamePda3 = PublicKey.findProgramAddressSync(
+      [Buffer.from("rps_game"), gameId3.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+    const game3 = await program.account.rpsGame.fetch(gamePda3);
+    expect(game3.movesSubmitted).to.deep.equal([true, false]);
+    expect(game3.state).to.deep.equal({ awaitingMoves: {} });
 
+    // Comparing is rejected until both players have a valid move in
     console.log("Comparing moves for invalid move test");
-    const compareTx3 = await program.methods
-      .compareMoves(compareComputationOffset3)
+    try {
+      const compareComputationOffset3 = new anchor.BN(randomBytes(8), "hex");
+      await program.methods
+        .compareMoves(compareComputationOffset3)
+        .accounts({
+          computationAccount: getComputationAccAddress(
+            arciumEnv.arciumClusterOffset,
+            compareComputationOffset3
+          ),
+          payer: playerA.publicKey,
+          mxeAccount: getMXEAccAddress(program.programId),
+          mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+          executingPool: getExecutingPoolAccAddress(
+            arciumEnv.arciumClusterOffset
+          ),
+          compDefAccount: getCompDefAccAddress(
+            program.programId,
+            Buffer.from(getCompDefAccOffset("compare_moves")).readUInt32LE()
+          ),
+          clusterAccount: clusterAccount,
+          rpsGame: gamePda3,
+          tournament: null,
+          nextGame: null,
+        })
+        .signers([playerA])
+        .rpc({ commitment: "confirmed" });
+
+      expect.fail("Compared moves before both players had moved");
+    } catch (error) {
+      console.log("Expected error caught:", error.message);
+      expect(error.message).to.include("InvalidGameState");
+    }
+  });
+
+  it("Plays a best-of-3 match", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const playerA = Keypair.generate();
+    const playerB = Keypair.generate();
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+
+    await airdrop(playerA.publicKey);
+    await airdrop(playerB.publicKey);
+
+    const matchId = new anchor.BN(randomBytes(8), "hex");
+    await initMatch(owner, matchId, playerA.publicKey, playerB.publicKey, 3);
+
+    // A wins round 1, round 2 is a tie, A wins round 3 and takes the match 2-0
+    const rounds = [
+      { a: 0, b: 2, expected: { playerAWins: {} } }, // Rock vs Scissors
+      { a: 1, b: 1, expected: { tie: {} } }, // Paper vs Paper
+      { a: 2, b: 1, expected: { playerAWins: {} } }, // Scissors vs Paper
+    ];
+
+    for (const [i, round] of rounds.entries()) {
+      console.log(`\n--- Round ${i + 1} ---`);
+      await submitMove(playerA, mxePublicKey, rpsGamePda(matchId), round.a);
+      if (i === 0) {
+        // The slot comes from the signer, so player A can't move twice
+        try {
+          await submitMove(playerA, mxePublicKey, rpsGamePda(matchId), 1);
+          expect.fail("Player A moved twice in one round");
+        } catch (error) {
+          expect(error.message).to.include("MoveAlreadySubmitted");
+        }
+      }
+      await submitMove(playerB, mxePublicKey, rpsGamePda(matchId), round.b);
+
+      const finishedPromise =
+        i === rounds.length - 1 ? awaitEvent("matchFinishedEvent") : null;
+      const roundEvent = await compareRound(playerA, rpsGamePda(matchId));
+      console.log("Round result:", roundEvent.result, roundEvent.score);
+
+      expect(roundEvent.result).to.deep.equal(round.expected);
+      expect(roundEvent.round).to.equal(i + 1);
+
+      if (finishedPromise) {
+        const finished = await finishedPromise;
+        expect(finished.winner.toBase58()).to.equal(
+          playerA.publicKey.toBase58()
+        );
+        expect(finished.score).to.deep.equal([2, 0]);
+      }
+    }
+
+    const game = await program.account.rpsGame.fetch(rpsGamePda(matchId));
+    expect(game.state).to.deep.equal({ resolved: {} });
+    expect(game.result).to.deep.equal({ playerAWins: {} });
+    expect(game.roundsPlayed).to.equal(3);
+    expect(game.roundResults).to.deep.equal(rounds.map((r) => r.expected));
+
+    // No more rounds can be compared once the match is over
+    try {
+      await compareRound(playerA, rpsGamePda(matchId));
+      expect.fail("Compared a round after the match finished");
+    } catch (error) {
+      expect(error.message).to.include("MatchFinished");
+    }
+  });
+
+  it("Awards a timed-out match to the player who moved", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const playerA = Keypair.generate();
+    const playerB = Keypair.generate();
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+
+    console.log("Initializing check_moves computation definition");
+    const checkMovesSig = await initCheckMovesCompDef(program, owner);
+    console.log(
+      "Check moves computation definition initialized with signature",
+      checkMovesSig
+    );
+
+    await airdrop(playerA.publicKey);
+    await airdrop(playerB.publicKey);
+
+    // Player A moves within a 20 second window, player B never does
+    const matchId = new anchor.BN(randomBytes(8), "hex");
+    await initMatch(
+      owner,
+      matchId,
+      playerA.publicKey,
+      playerB.publicKey,
+      1,
+      20
+    );
+    await submitMove(playerA, mxePublicKey, rpsGamePda(matchId), 0);
+
+    const game = await program.account.rpsGame.fetch(rpsGamePda(matchId));
+    const waitMs = (game.moveDeadline.toNumber() + 2) * 1000 - Date.now();
+    if (waitMs > 0) {
+      console.log(`Waiting ${waitMs}ms for the move deadline to pass`);
+      await new Promise((resolve) => setTimeout(resolve, waitMs));
+    }
+
+    // Player B can no longer move once the deadline has passed
+    try {
+      await submitMove(playerB, mxePublicKey, rpsGamePda(matchId), 1);
+      expect.fail("Player B moved after the deadline");
+    } catch (error) {
+      expect(error.message).to.include("MoveDeadlinePassed");
+    }
+
+    const timeoutEventPromise = awaitEvent("timeoutClaimedEvent");
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .claimTimeout(computationOffset)
       .accounts({
         computationAccount: getComputationAccAddress(
           arciumEnv.arciumClusterOffset,
-          compareComputationOffset3
+          computationOffset
         ),
-        payer: owner.publicKey,
+        payer: playerA.publicKey,
         mxeAccount: getMXEAccAddress(program.programId),
         mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
         executingPool: getExecutingPoolAccAddress(
@@ -958,35 +1149,833 @@ describe("RockPaperScissors", () => {
         ),
         compDefAccount: getCompDefAccAddress(
           program.programId,
-          Buffer.from(getCompDefAccOffset("compare_moves")).readUInt32LE()
+          Buffer.from(getCompDefAccOffset("check_moves")).readUInt32LE()
         ),
         clusterAccount: clusterAccount,
-        rpsGame: PublicKey.findProgramAddressSync(
-          [Buffer.from("rps_game"), gameId3.toArrayLike(Buffer, "le", 8)],
-          program.programId
-        )[0],
+        rpsGame: rpsGamePda(matchId),
+        tournament: null,
+        nextGame: null,
       })
-      .rpc({
-        skipPreflight: true,
-        commitment: "confirmed",
-      });
-
-    console.log("Compare moves signature for invalid move test:", compareTx3);
+      .signers([playerA])
+      .rpc({ commitment: "confirmed" });
 
-    const finalizeSig3 = await awaitComputationFinalization(
+    await awaitComputationFinalization(
       provider as anchor.AnchorProvider,
-      compareComputationOffset3,
+      computationOffset,
       program.programId,
       "confirmed"
     );
-    console.log("Finalize signature for invalid move test:", finalizeSig3);
 
-    const gameEvent3 = await gameEventPromise3;
-    console.log(`Game result for invalid move test: ${gameEvent3.result}`);
+    const timeoutEvent = await timeoutEventPromise;
+    expect(timeoutEvent.playerAMoved).to.equal(true);
+    expect(timeoutEvent.playerBMoved).to.equal(false);
+    expect(timeoutEvent.winner.toBase58()).to.equal(
+      playerA.publicKey.toBase58()
+    );
+
+    const finishedGame = await program.account.rpsGame.fetch(
+      rpsGamePda(matchId)
+    );
+    expect(finishedGame.state).to.deep.equal({ resolved: {} });
+    expect(finishedGame.result).to.deep.equal({ playerAWins: {} });
+
+    // The forfeited match accepts no more moves
+    try {
+      await submitMove(playerB, mxePublicKey, rpsGamePda(matchId), 1);
+      expect.fail("Player B moved after the match was forfeited");
+    } catch (error) {
+      expect(error.message).to.include("InvalidGameState");
+    }
+  });
+
+  it("Matches players through the lobby", async () => {
+    const playerA = Keypair.generate();
+    const playerB = Keypair.generate();
+
+    await airdrop(playerA.publicKey);
+    await airdrop(playerB.publicKey);
+
+    const lobbyPda = PublicKey.findProgramAddressSync(
+      [Buffer.from("lobby")],
+      program.programId
+    )[0];
+
+    // Player A opens a game with only their own key and a stake
+    const gameId = new anchor.BN(randomBytes(8), "hex");
+    const gamePda = await createLobbyGame(playerA, gameId);
+
+    let lobby = await program.account.lobby.fetch(lobbyPda);
+    const listing = lobby.openGames.find((l) => l.game.equals(gamePda));
+    expect(listing).to.not.be.undefined;
+    expect(listing.expiresAt.toNumber()).to.be.greaterThan(Date.now() / 1000);
+
+    // Pruning only removes expired listings, so the fresh game stays listed
+    await program.methods
+      .pruneLobby()
+      .accountsPartial({ lobby: lobbyPda })
+      .rpc({ commitment: "confirmed" });
+    lobby = await program.account.lobby.fetch(lobbyPda);
+    expect(lobby.openGames.map((l) => l.game.toBase58())).to.include(
+      gamePda.toBase58()
+    );
+
+    // Player A can't join their own game
+    try {
+      await program.methods
+        .joinGame()
+        .accountsPartial({ player: playerA.publicKey, rpsGame: gamePda })
+        .signers([playerA])
+        .rpc({ commitment: "confirmed" });
+      expect.fail("Player A joined their own game");
+    } catch (error) {
+      expect(error.message).to.include("CannotJoinOwnGame");
+    }
+
+    // Player B finds it in the lobby and joins
+    const joinedEventPromise = awaitEvent("gameJoinedEvent");
+    await program.methods
+      .joinGame()
+      .accountsPartial({ player: playerB.publicKey, rpsGame: gamePda })
+      .signers([playerB])
+      .rpc({ commitment: "confirmed" });
+
+    const joinedEvent = await joinedEventPromise;
+    expect(joinedEvent.playerB.toBase58()).to.equal(
+      playerB.publicKey.toBase58()
+    );
+
+    const game = await program.account.rpsGame.fetch(gamePda);
+    expect(game.playerA.toBase58()).to.equal(playerA.publicKey.toBase58());
+    expect(game.playerB.toBase58()).to.equal(playerB.publicKey.toBase58());
+    expect(game.state).to.deep.equal({ awaitingMoves: {} });
+
+    lobby = await program.account.lobby.fetch(lobbyPda);
+    expect(lobby.openGames.map((l) => l.game.toBase58())).to.not.include(
+      gamePda.toBase58()
+    );
+
+    // An unjoined game can be cancelled by its creator, closing the account
+    const cancelledId = new anchor.BN(randomBytes(8), "hex");
+    const cancelledPda = await createLobbyGame(playerA, cancelledId);
+
+    try {
+      await program.methods
+        .cancelGame()
+        .accountsPartial({ creator: playerB.publicKey, rpsGame: cancelledPda })
+        .signers([playerB])
+        .rpc({ commitment: "confirmed" });
+      expect.fail("Player B cancelled player A's game");
+    } catch (error) {
+      expect(error.message).to.include("NotAuthorized");
+    }
+
+    await program.methods
+      .cancelGame()
+      .accountsPartial({ creator: playerA.publicKey, rpsGame: cancelledPda })
+      .signers([playerA])
+      .rpc({ commitment: "confirmed" });
+
+    expect(await program.account.rpsGame.fetchNullable(cancelledPda)).to.be
+      .null;
+    lobby = await program.account.lobby.fetch(lobbyPda);
+    expect(lobby.openGames.map((l) => l.game.toBase58())).to.not.include(
+      cancelledPda.toBase58()
+    );
+  });
+
+  it("Pays the winner from the escrowed stakes", async () => {
+    const playerA = Keypair.generate();
+    const playerB = Keypair.generate();
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+
+    await airdrop(playerA.publicKey);
+    await airdrop(playerB.publicKey);
+
+    // Both players put up 0.1 SOL: player A on create, player B on join
+    const stake = anchor.web3.LAMPORTS_PER_SOL / 10;
+    const gameId = new anchor.BN(randomBytes(8), "hex");
+    const gamePda = await createLobbyGame(playerA, gameId);
+    await program.methods
+      .joinGame()
+      .accountsPartial({ player: playerB.publicKey, rpsGame: gamePda })
+      .signers([playerB])
+      .rpc({ commitment: "confirmed" });
+
+    const vaultPda = PublicKey.findProgramAddressSync(
+      [Buffer.from("game_vault"), gamePda.toBuffer()],
+      program.programId
+    )[0];
+    const vaultRent =
+      await provider.connection.getMinimumBalanceForRentExemption(8 + 1);
+    expect(await provider.connection.getBalance(vaultPda)).to.equal(
+      vaultRent + 2 * stake
+    );
+
+    // Rock beats Scissors
+    await submitMove(playerA, mxePublicKey, gamePda, 0);
+    await submitMove(playerB, mxePublicKey, gamePda, 2);
+
+    const treasuryBefore = await provider.connection.getBalance(
+      treasury.publicKey
+    );
+    const playerABefore = await provider.connection.getBalance(
+      playerA.publicKey
+    );
+
+    const settledPromise = awaitEvent("stakesSettledEvent");
+    await compareRound(playerB, gamePda);
+    const settled = await settledPromise;
+
+    // The winner takes the pot less the 2% protocol fee
+    const fee = (2 * stake * protocolFeeBps) / 10000;
+    expect(settled.fee.toNumber()).to.equal(fee);
+    expect(settled.payouts.map((p) => p.toNumber())).to.deep.equal([
+      2 * stake - fee,
+      0,
+    ]);
+
+    expect(await provider.connection.getBalance(vaultPda)).to.equal(vaultRent);
+    expect(
+      (await provider.connection.getBalance(treasury.publicKey)) -
+        treasuryBefore
+    ).to.equal(fee);
+    expect(
+      (await provider.connection.getBalance(playerA.publicKey)) - playerABefore
+    ).to.equal(2 * stake - fee);
+  });
+
+  it("Runs a four-player tournament", async () => {
+    const organizer = Keypair.generate();
+    const entrants = [0, 1, 2, 3].map(() => Keypair.generate());
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+
+    await airdrop(organizer.publicKey);
+    for (const entrant of entrants) {
+      await airdrop(entrant.publicKey);
+    }
+
+    const entryFee = anchor.web3.LAMPORTS_PER_SOL / 10;
+    const tournamentId = new anchor.BN(randomBytes(8), "hex");
+    const tournamentPda = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("tournament"),
+        organizer.publicKey.toBuffer(),
+        tournamentId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    )[0];
+
+    // 70% of the pool to the champion, 30% to the runner-up
+    await program.methods
+      .createTournament(
+        tournamentId,
+        4,
+        new anchor.BN(entryFee),
+        1,
+        new anchor.BN(3600),
+        [7000, 3000]
+      )
+      .accountsPartial({ organizer: organizer.publicKey })
+      .signers([organizer])
+      .rpc({ commitment: "confirmed" });
+
+    for (const entrant of entrants) {
+      await program.methods
+        .register()
+        .accountsPartial({
+          entrant: entrant.publicKey,
+          tournament: tournamentPda,
+        })
+        .signers([entrant])
+        .rpc({ commitment: "confirmed" });
+    }
+
+    try {
+      await program.methods
+        .register()
+        .accountsPartial({
+          entrant: organizer.publicKey,
+          tournament: tournamentPda,
+        })
+        .signers([organizer])
+        .rpc({ commitment: "confirmed" });
+      expect.fail("A full tournament should not take more entrants");
+    } catch (error) {
+      expect(error.message).to.include("InvalidTournamentState");
+    }
+
+    // Match 1 is the final, fed by the first-round matches 2 and 3. Any
+    // entrant can create them, not just the organizer.
+    for (const matchIndex of [1, 2, 3]) {
+      await createBracketGame(entrants[matchIndex], tournamentPda, matchIndex);
+    }
+    const bracketGame = (matchIndex: number) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("rps_game"),
+          tournamentPda.toBuffer(),
+          Buffer.from([matchIndex]),
+        ],
+        program.programId
+      )[0];
+
+    let tournament = await program.account.tournament.fetch(tournamentPda);
+    expect(tournament.state).to.deep.equal({ inProgress: {} });
+    let final = await program.account.rpsGame.fetch(bracketGame(1));
+    expect(final.state).to.deep.equal({ awaitingPlayers: {} });
+
+    // Rock beats Scissors: entrant 0 takes match 2 into the final as player A
+    await submitMove(entrants[0], mxePublicKey, bracketGame(2), 0);
+    await submitMove(entrants[1], mxePublicKey, bracketGame(2), 2);
+    await compareRound(entrants[0], bracketGame(2), {
+      tournament: tournamentPda,
+      nextGame: bracketGame(1),
+    });
+
+    // Paper beats Rock: entrant 2 takes match 3 into the final as player B
+    await submitMove(entrants[2], mxePublicKey, bracketGame(3), 1);
+    await submitMove(entrants[3], mxePublicKey, bracketGame(3), 0);
+    await compareRound(entrants[2], bracketGame(3), {
+      tournament: tournamentPda,
+      nextGame: bracketGame(1),
+    });
+
+    final = await program.account.rpsGame.fetch(bracketGame(1));
+    expect(final.playerA.toBase58()).to.equal(entrants[0].publicKey.toBase58());
+    expect(final.playerB.toBase58()).to.equal(entrants[2].publicKey.toBase58());
+    expect(final.state).to.deep.equal({ awaitingMoves: {} });
+
+    // A tied final is replayed rather than ending level
+    await submitMove(entrants[0], mxePublicKey, bracketGame(1), 0);
+    await submitMove(entrants[2], mxePublicKey, bracketGame(1), 0);
+    await compareRound(entrants[0], bracketGame(1), {
+      tournament: tournamentPda,
+      nextGame: null,
+    });
+    final = await program.account.rpsGame.fetch(bracketGame(1));
+    expect(final.state).to.deep.equal({ awaitingMoves: {} });
+
+    const finishedPromise = awaitEvent("tournamentFinishedEvent");
+    await submitMove(entrants[0], mxePublicKey, bracketGame(1), 2);
+    await submitMove(entrants[2], mxePublicKey, bracketGame(1), 0);
+    await compareRound(entrants[0], bracketGame(1), {
+      tournament: tournamentPda,
+      nextGame: null,
+    });
+    const finished = await finishedPromise;
+    expect(finished.champion.toBase58()).to.equal(
+      entrants[2].publicKey.toBase58()
+    );
+    expect(finished.runnerUp.toBase58()).to.equal(
+      entrants[0].publicKey.toBase58()
+    );
+
+    const championBefore = await provider.connection.getBalance(
+      entrants[2].publicKey
+    );
+    const runnerUpBefore = await provider.connection.getBalance(
+      entrants[0].publicKey
+    );
+    await program.methods
+      .distributePrizes()
+      .accountsPartial({
+        tournament: tournamentPda,
+        champion: entrants[2].publicKey,
+        runnerUp: entrants[0].publicKey,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const pool = 4 * entryFee;
+    expect(
+      (await provider.connection.getBalance(entrants[2].publicKey)) -
+        championBefore
+    ).to.equal((pool * 7000) / 10000);
+    expect(
+      (await provider.connection.getBalance(entrants[0].publicKey)) -
+        runnerUpBefore
+    ).to.equal((pool * 3000) / 10000);
+
+    tournament = await program.account.tournament.fetch(tournamentPda);
+    expect(tournament.state).to.deep.equal({ prizesPaid: {} });
+  });
+
+  it("Refunds entrants of a cancelled tournament", async () => {
+    const organizer = Keypair.generate();
+    const entrant = Keypair.generate();
+
+    await airdrop(organizer.publicKey);
+    await airdrop(entrant.publicKey);
+
+    const entryFee = anchor.web3.LAMPORTS_PER_SOL / 10;
+    const tournamentId = new anchor.BN(randomBytes(8), "hex");
+    const tournamentPda = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("tournament"),
+        organizer.publicKey.toBuffer(),
+        tournamentId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    )[0];
+
+    await program.methods
+      .createTournament(
+        tournamentId,
+        4,
+        new anchor.BN(entryFee),
+        1,
+        new anchor.BN(3600),
+        [7000, 3000]
+      )
+      .accountsPartial({ organizer: organizer.publicKey })
+      .signers([organizer])
+      .rpc({ commitment: "confirmed" });
+    await program.methods
+      .register()
+      .accountsPartial({
+        entrant: entrant.publicKey,
+        tournament: tournamentPda,
+      })
+      .signers([entrant])
+      .rpc({ commitment: "confirmed" });
+
+    // Only the organizer can call it off
+    try {
+      await program.methods
+        .cancelTournament()
+        .accountsPartial({
+          organizer: entrant.publicKey,
+          tournament: tournamentPda,
+        })
+        .signers([entrant])
+        .rpc({ commitment: "confirmed" });
+      expect.fail("An entrant cancelled the tournament");
+    } catch (error) {
+      expect(error.message).to.include("NotAuthorized");
+    }
+
+    // The bracket never fills, so the organizer cancels it
+    await program.methods
+      .cancelTournament()
+      .accountsPartial({
+        organizer: organizer.publicKey,
+        tournament: tournamentPda,
+      })
+      .signers([organizer])
+      .rpc({ commitment: "confirmed" });
+
+    const entrantBefore = await provider.connection.getBalance(
+      entrant.publicKey
+    );
+    await program.methods
+      .withdrawEntry()
+      .accountsPartial({
+        entrant: entrant.publicKey,
+        tournament: tournamentPda,
+      })
+      .signers([entrant])
+      .rpc({ commitment: "confirmed" });
+    expect(
+      (await provider.connection.getBalance(entrant.publicKey)) -
+        entrantBefore
+    ).to.equal(entryFee);
+
+    // Each entry is refunded once
+    try {
+      await program.methods
+        .withdrawEntry()
+        .accountsPartial({
+          entrant: entrant.publicKey,
+          tournament: tournamentPda,
+        })
+        .signers([entrant])
+        .rpc({ commitment: "confirmed" });
+      expect.fail("Withdrew the same entry twice");
+    } catch (error) {
+      expect(error.message).to.include("NotRegistered");
+    }
+
+    const tournament = await program.account.tournament.fetch(tournamentPda);
+    expect(tournament.state).to.deep.equal({ cancelled: {} });
+    expect(tournament.entrants).to.be.empty;
+  });
+
+  it("Cancels a stalled tournament after a no-show", async () => {
+    const organizer = Keypair.generate();
+    const entrants = [0, 1, 2, 3].map(() => Keypair.generate());
+
+    await airdrop(organizer.publicKey);
+    for (const entrant of entrants) {
+      await airdrop(entrant.publicKey);
+    }
+
+    const entryFee = anchor.web3.LAMPORTS_PER_SOL / 10;
+    const tournamentId = new anchor.BN(randomBytes(8), "hex");
+    const tournamentPda = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("tournament"),
+        organizer.publicKey.toBuffer(),
+        tournamentId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    )[0];
+
+    // Every bracket game gives its players a 20 second move window
+    await program.methods
+      .createTournament(
+        tournamentId,
+        4,
+        new anchor.BN(entryFee),
+        1,
+        new anchor.BN(20),
+        [7000, 3000]
+      )
+      .accountsPartial({ organizer: organizer.publicKey })
+      .signers([organizer])
+      .rpc({ commitment: "confirmed" });
+    for (const entrant of entrants) {
+      await program.methods
+        .register()
+        .accountsPartial({
+          entrant: entrant.publicKey,
+          tournament: tournamentPda,
+        })
+        .signers([entrant])
+        .rpc({ commitment: "confirmed" });
+    }
+    for (const matchIndex of [1, 2, 3]) {
+      await createBracketGame(organizer, tournamentPda, matchIndex);
+    }
+    const bracketGame = (matchIndex: number) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("rps_game"),
+          tournamentPda.toBuffer(),
+          Buffer.from([matchIndex]),
+        ],
+        program.programId
+      )[0];
+
+    // Neither player of match 2 moves before the deadline
+    const game = await program.account.rpsGame.fetch(bracketGame(2));
+    const waitMs = (game.moveDeadline.toNumber() + 2) * 1000 - Date.now();
+    if (waitMs > 0) {
+      console.log(`Waiting ${waitMs}ms for the move deadline to pass`);
+      await new Promise((resolve) => setTimeout(resolve, waitMs));
+    }
+
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .claimTimeout(computationOffset)
+      .accountsPartial({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        payer: entrants[1].publicKey,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("check_moves")).readUInt32LE()
+        ),
+        clusterAccount: clusterAccount,
+        rpsGame: bracketGame(2),
+        tournament: tournamentPda,
+        nextGame: bracketGame(1),
+      })
+      .signers([entrants[1]])
+      .rpc({ commitment: "confirmed" });
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
 
-    // Verify the result is "Invalid Move"
-    expect(gameEvent3.result).to.equal("Invalid Move");
+    const abandoned = await program.account.rpsGame.fetch(bracketGame(2));
+    expect(abandoned.result).to.deep.equal({ playerAWins: {} });
+    const final = await program.account.rpsGame.fetch(bracketGame(1));
+    expect(final.playerA.toBase58()).to.equal(entrants[0].publicKey.toBase58());
+
+    // The organizer calls off the stalled tournament and everyone is refunded
+    await program.methods
+      .cancelTournament()
+      .accountsPartial({
+        organizer: organizer.publicKey,
+        tournament: tournamentPda,
+      })
+      .signers([organizer])
+      .rpc({ commitment: "confirmed" });
+    for (const entrant of entrants) {
+      const before = await provider.connection.getBalance(entrant.publicKey);
+      await program.methods
+        .withdrawEntry()
+        .accountsPartial({
+          entrant: entrant.publicKey,
+          tournament: tournamentPda,
+        })
+        .signers([entrant])
+        .rpc({ commitment: "confirmed" });
+      expect(
+        (await provider.connection.getBalance(entrant.publicKey)) - before
+      ).to.equal(entryFee);
+    }
   });
+
+  async function createLobbyGame(
+    creator: Keypair,
+    gameId: anchor.BN
+  ): Promise<PublicKey> {
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .createGame(
+        computationOffset,
+        gameId,
+        new anchor.BN(anchor.web3.LAMPORTS_PER_SOL / 10),
+        1,
+        new anchor.BN(3600)
+      )
+      .accountsPartial({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        payer: creator.publicKey,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("init_game")).readUInt32LE()
+        ),
+        clusterAccount: clusterAccount,
+      })
+      .signers([creator])
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+
+    return PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("rps_game"),
+        creator.publicKey.toBuffer(),
+        gameId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    )[0];
+  }
+
+  async function createBracketGame(
+    payer: Keypair,
+    tournament: PublicKey,
+    matchIndex: number
+  ) {
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .createBracketGame(computationOffset, matchIndex)
+      .accountsPartial({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        payer: payer.publicKey,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("init_game")).readUInt32LE()
+        ),
+        clusterAccount: clusterAccount,
+        tournament,
+      })
+      .signers([payer])
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+  }
+
+  function rpsGamePda(gameId: anchor.BN): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("rps_game"), gameId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+  }
+
+  async function airdrop(pubkey: PublicKey) {
+    const sig = await provider.connection.requestAirdrop(
+      pubkey,
+      2 * anchor.web3.LAMPORTS_PER_SOL
+    );
+    const latest = await provider.connection.getLatestBlockhash();
+    await provider.connection.confirmTransaction({ signature: sig, ...latest });
+  }
+
+  async function initMatch(
+    payer: Keypair,
+    gameId: anchor.BN,
+    playerA: PublicKey,
+    playerB: PublicKey,
+    bestOf: number,
+    moveTimeout = 3600
+  ) {
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .initGame(
+        computationOffset,
+        gameId,
+        playerA,
+        playerB,
+        new anchor.BN(0),
+        bestOf,
+        new anchor.BN(moveTimeout)
+      )
+      .accounts({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        payer: payer.publicKey,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("init_game")).readUInt32LE()
+        ),
+        clusterAccount: clusterAccount,
+      })
+      .signers([payer])
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+  }
+
+  async function submitMove(
+    player: Keypair,
+    mxePublicKey: Uint8Array,
+    rpsGame: PublicKey,
+    move: number
+  ) {
+    const privateKey = x25519.utils.randomSecretKey();
+    const publicKey = x25519.getPublicKey(privateKey);
+    const cipher = new RescueCipher(
+      x25519.getSharedSecret(privateKey, mxePublicKey)
+    );
+    const nonce = randomBytes(16);
+    const ciphertext = cipher.encrypt([BigInt(move)], nonce);
+
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .playerMove(
+        computationOffset,
+        Array.from(ciphertext[0]),
+        Array.from(publicKey),
+        new anchor.BN(deserializeLE(nonce).toString())
+      )
+      .accounts({
+        payer: player.publicKey,
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("player_move")).readUInt32LE()
+        ),
+        clusterAccount: clusterAccount,
+        rpsGame,
+      })
+      .signers([player])
+      .rpc({ commitment: "confirmed" });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+  }
+
+  async function compareRound(
+    payer: Keypair,
+    rpsGame: PublicKey,
+    bracket: { tournament: PublicKey; nextGame: PublicKey | null } | null = null
+  ) {
+    const eventPromise = awaitEvent("compareMovesEvent");
+    // Keep a rejected compare from leaving an unhandled event timeout behind
+    eventPromise.catch(() => {});
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .compareMoves(computationOffset)
+      .accounts({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        payer: payer.publicKey,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("compare_moves")).readUInt32LE()
+        ),
+        clusterAccount: clusterAccount,
+        rpsGame,
+        tournament: bracket?.tournament ?? null,
+        nextGame: bracket?.nextGame ?? null,
+      })
+      .signers([payer])
+      .rpc({ commitment: "confirmed" });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+    return eventPromise;
+  }
 });
 
 // Helper function to read keypair from JSON file
@@ -1160,6 +2149,60 @@ async function initCompareMovesCompDef(
   return sig;
 }
 
+async function initCheckMovesCompDef(
+  program: Program<RockPaperScissors>,
+  owner: anchor.web3.Keypair
+): Promise<string> {
+  const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+    "ComputationDefinitionAccount"
+  );
+  const offset = getCompDefAccOffset("check_moves");
+
+  const compDefPDA = PublicKey.findProgramAddressSync(
+    [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+    getArciumProgramId()
+  )[0];
+
+  console.log(`Comp def PDA for check_moves:`, compDefPDA.toBase58());
+
+  const arciumProgram = getArciumProgram(
+    program.provider as anchor.AnchorProvider
+  );
+  const mxeAccount = getMXEAccAddress(program.programId);
+  const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+  const lutAddress = getLookupTableAddress(
+    program.programId,
+    mxeAcc.lutOffsetSlot
+  );
+
+  const sig = await program.methods
+    .initCheckMovesCompDef()
+    .accounts({
+      compDefAccount: compDefPDA,
+      payer: owner.publicKey,
+      mxeAccount,
+      addressLookupTable: lutAddress,
+    })
+    .signers([owner])
+    .rpc({
+      preflightCommitment: "confirmed",
+      commitment: "confirmed",
+    });
+
+  console.log(`Init check_moves computation definition transaction`, sig);
+
+  const rawCircuit = fs.readFileSync(`build/check_moves.arcis`);
+  await uploadCircuit(
+    program.provider as anchor.AnchorProvider,
+    "check_moves",
+    program.programId,
+    rawCircuit,
+    true
+  );
+
+  return sig;
+}
+
 async function getMXEPublicKeyWithRetry(
   provider: anchor.AnchorProvider,
   programId: PublicKey,
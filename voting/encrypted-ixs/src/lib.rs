@@ -4,71 +4,435 @@ use arcis::*;
 mod circuits {
     use arcis::*;
 
+    /// Most options a poll can offer.
+    const MAX_OPTIONS: usize = 8;
+    /// Most options a ranked-choice poll can offer.
+    const MAX_RANKED_OPTIONS: usize = 4;
+    /// Elimination rounds needed to get from every option down to the final two.
+    const IRV_ROUNDS: usize = MAX_RANKED_OPTIONS - 1;
+    /// Counters kept for a ranked-choice poll: one per option still standing, for every
+    /// set of eliminated options an instant-runoff count can reach.
+    const RANKED_SLOTS: usize = 28;
+
+    /// Running counters of a ranked-choice poll, packed three to a field element.
+    type RankedCounts = Pack<[u64; RANKED_SLOTS]>;
+
     /// Tracks the encrypted vote tallies for a poll.
     pub struct VoteStats {
-        yes: u64,
-        no: u64,
+        /// Vote weight for each option. Slots past the poll's option count stay at zero.
+        counts: [u64; MAX_OPTIONS],
+        /// Weight of ballots whose option index was out of range
+        invalid: u64,
     }
 
     /// Represents a single encrypted vote.
     pub struct UserVote {
-        vote: bool,
+        /// Index of the chosen option
+        option: u8,
+    }
+
+    /// An encrypted quadratic-voting ballot.
+    pub struct QuadraticBallot {
+        /// Votes given to each option slot. Casting `n` votes costs `n * n` credits.
+        votes: [u8; MAX_OPTIONS],
+    }
+
+    /// An encrypted ranked-choice ballot.
+    pub struct RankedBallot {
+        /// Option indices from most to least preferred. Any value past the poll's options
+        /// marks an unused position.
+        ranking: [u8; MAX_RANKED_OPTIONS],
     }
 
     /// Initializes encrypted vote counters for a new poll.
     ///
-    /// Creates a VoteStats structure with zero counts for both yes and no votes.
+    /// Creates a VoteStats structure with zero counts for every option.
     /// The counters remain encrypted and can only be updated through MPC operations.
     #[instruction]
     pub fn init_vote_stats() -> Enc<Mxe, VoteStats> {
-        let vote_stats = VoteStats { yes: 0, no: 0 };
+        let vote_stats = VoteStats {
+            counts: [0; MAX_OPTIONS],
+            invalid: 0,
+        };
         Mxe::get().from_arcis(vote_stats)
     }
 
     /// Processes an encrypted vote and updates the running tallies.
     ///
-    /// Takes an individual vote and adds it to the appropriate counter (yes or no)
-    /// without revealing the vote value. The updated vote statistics remain encrypted
-    /// and can only be revealed by the poll authority.
+    /// Takes an individual vote and adds its weight to the counter of the chosen option
+    /// without revealing the vote value. A ballot naming an option the poll doesn't
+    /// have is counted as invalid instead, so a bad ballot can't be told apart from
+    /// a good one. The updated vote statistics remain encrypted and can only be
+    /// revealed by the poll authority.
     ///
     /// # Arguments
     /// * `vote_ctxt` - The encrypted vote to be counted
     /// * `vote_stats_ctxt` - Current encrypted vote tallies
+    /// * `num_options` - Number of options the poll offers
+    /// * `weight` - Public weight of the vote: 1, or the tokens the voter locked
     ///
     /// # Returns
-    /// Updated encrypted vote statistics with the new vote included
+    /// * Updated encrypted vote statistics with the new vote included
+    /// * The vote re-encrypted for the MXE, so it can be taken back out by `change_vote`
     #[instruction]
     pub fn vote(
         vote_ctxt: Enc<Shared, UserVote>,
         vote_stats_ctxt: Enc<Mxe, VoteStats>,
-    ) -> Enc<Mxe, VoteStats> {
+        num_options: u8,
+        weight: u64,
+    ) -> (Enc<Mxe, VoteStats>, Enc<Mxe, UserVote>) {
+        let user_vote = vote_ctxt.to_arcis();
+        let mut vote_stats = vote_stats_ctxt.to_arcis();
+
+        add_vote(&mut vote_stats, &user_vote, num_options, weight);
+
+        (
+            vote_stats_ctxt.owner.from_arcis(vote_stats),
+            Mxe::get().from_arcis(user_vote),
+        )
+    }
+
+    /// Replaces a voter's earlier vote with a new one.
+    ///
+    /// Takes the recorded vote back out of the running tallies and adds the new vote in
+    /// its place. Both updates visit every counter, so nobody can tell which option was
+    /// left or which was picked, or whether the vote changed at all.
+    ///
+    /// # Arguments
+    /// * `vote_ctxt` - The new encrypted vote
+    /// * `recorded_vote_ctxt` - The voter's current vote, as recorded by `vote` or an earlier change
+    /// * `vote_stats_ctxt` - Current encrypted vote tallies
+    /// * `num_options` - Number of options the poll offers
+    /// * `recorded_weight` - Weight the current vote was counted with
+    /// * `weight` - Public weight of the new vote: 1, or the tokens the voter locked
+    ///
+    /// # Returns
+    /// * Updated encrypted vote statistics counting the new vote instead of the old one
+    /// * The new vote encrypted for the MXE, to be recorded in its place
+    #[instruction]
+    pub fn change_vote(
+        vote_ctxt: Enc<Shared, UserVote>,
+        recorded_vote_ctxt: Enc<Mxe, UserVote>,
+        vote_stats_ctxt: Enc<Mxe, VoteStats>,
+        num_options: u8,
+        recorded_weight: u64,
+        weight: u64,
+    ) -> (Enc<Mxe, VoteStats>, Enc<Mxe, UserVote>) {
         let user_vote = vote_ctxt.to_arcis();
+        let recorded_vote = recorded_vote_ctxt.to_arcis();
         let mut vote_stats = vote_stats_ctxt.to_arcis();
 
-        // Increment appropriate counter based on vote value
-        if user_vote.vote {
-            vote_stats.yes += 1;
-        } else {
-            vote_stats.no += 1;
+        // The recorded vote was added with the same rules, so nothing here can underflow
+        for i in 0..MAX_OPTIONS {
+            if recorded_vote.option == i as u8 && recorded_vote.option < num_options {
+                vote_stats.counts[i] -= recorded_weight;
+            }
+        }
+        if recorded_vote.option >= num_options {
+            vote_stats.invalid -= recorded_weight;
+        }
+        add_vote(&mut vote_stats, &user_vote, num_options, weight);
+
+        (
+            vote_stats_ctxt.owner.from_arcis(vote_stats),
+            recorded_vote_ctxt.owner.from_arcis(user_vote),
+        )
+    }
+
+    /// Adds `weight` to the counter of the chosen option, or to the invalid counter if the
+    /// poll doesn't have that option. Every counter is visited so the chosen option stays hidden.
+    fn add_vote(vote_stats: &mut VoteStats, user_vote: &UserVote, num_options: u8, weight: u64) {
+        for i in 0..MAX_OPTIONS {
+            if user_vote.option == i as u8 && user_vote.option < num_options {
+                vote_stats.counts[i] += weight;
+            }
+        }
+        if user_vote.option >= num_options {
+            vote_stats.invalid += weight;
+        }
+    }
+
+    /// Processes an encrypted quadratic-voting ballot and updates the running tallies.
+    ///
+    /// The voter splits their votes across options, paying the square of the votes given
+    /// to each one. A ballot within the credit budget that only names the poll's options
+    /// has each option's votes added to its counter. Any other ballot adds nothing but its
+    /// weight to the invalid counter. Both cases do the same work on every counter, so a
+    /// rejected ballot can't be told apart from an accepted one, nor its contents learned.
+    ///
+    /// # Arguments
+    /// * `ballot_ctxt` - The encrypted ballot to be counted
+    /// * `vote_stats_ctxt` - Current encrypted vote tallies
+    /// * `num_options` - Number of options the poll offers
+    /// * `credits` - Credit budget of each voter
+    /// * `weight` - Public weight of the ballot: 1, or the tokens the voter locked
+    ///
+    /// # Returns
+    /// Updated encrypted vote statistics with the ballot included
+    #[instruction]
+    pub fn vote_quadratic(
+        ballot_ctxt: Enc<Shared, QuadraticBallot>,
+        vote_stats_ctxt: Enc<Mxe, VoteStats>,
+        num_options: u8,
+        credits: u64,
+        weight: u64,
+    ) -> Enc<Mxe, VoteStats> {
+        let ballot = ballot_ctxt.to_arcis();
+        let mut vote_stats = vote_stats_ctxt.to_arcis();
+
+        let mut cost: u64 = 0;
+        let mut names_missing_option = false;
+        for i in 0..MAX_OPTIONS {
+            let votes = ballot.votes[i] as u64;
+            cost += votes * votes;
+            if i as u8 >= num_options && votes > 0 {
+                names_missing_option = true;
+            }
+        }
+        let valid = cost <= credits && !names_missing_option;
+
+        for i in 0..MAX_OPTIONS {
+            if valid {
+                vote_stats.counts[i] += ballot.votes[i] as u64 * weight;
+            }
+        }
+        if !valid {
+            vote_stats.invalid += weight;
         }
 
         vote_stats_ctxt.owner.from_arcis(vote_stats)
     }
 
+    /// Initializes the encrypted counters of a ranked-choice poll, all at zero.
+    #[instruction]
+    pub fn init_ranked_stats() -> Enc<Mxe, RankedCounts> {
+        Mxe::get().from_arcis(Pack::new([0; RANKED_SLOTS]))
+    }
+
+    /// Processes an encrypted ranked-choice ballot and updates the running counters.
+    ///
+    /// Instant-runoff only ever needs to know, for the set of options eliminated so far,
+    /// which remaining option each ballot ranks highest. So instead of storing ballots, the
+    /// ballot's weight is added to that option's counter for every set of eliminated options
+    /// the count can reach. A ballot ranking none of the remaining options adds nothing
+    /// for that set. Every counter is visited so the ranking stays hidden.
+    ///
+    /// # Arguments
+    /// * `ballot_ctxt` - The encrypted ranking to be counted
+    /// * `ranked_ctxt` - Current encrypted counters
+    /// * `num_options` - Number of options the poll offers
+    /// * `weight` - Public weight of the ballot: 1, or the tokens the voter locked
+    ///
+    /// # Returns
+    /// Updated encrypted counters with the ballot included
+    #[instruction]
+    pub fn vote_ranked(
+        ballot_ctxt: Enc<Shared, RankedBallot>,
+        ranked_ctxt: Enc<Mxe, RankedCounts>,
+        num_options: u8,
+        weight: u64,
+    ) -> Enc<Mxe, RankedCounts> {
+        let ballot = ballot_ctxt.to_arcis();
+        let mut counts = ranked_ctxt.to_arcis().unpack();
+
+        let mut slot = 0;
+        for eliminated in 0..1 << MAX_RANKED_OPTIONS {
+            if eliminated_count(eliminated) <= IRV_ROUNDS - 1 {
+                // Walk the ranking from the bottom so the most preferred remaining option wins
+                let mut top = MAX_RANKED_OPTIONS as u8;
+                for position in (0..MAX_RANKED_OPTIONS).rev() {
+                    let choice = ballot.ranking[position];
+                    let mut remaining = choice < num_options;
+                    for option in 0..MAX_RANKED_OPTIONS {
+                        if eliminated & (1 << option) != 0 && choice == option as u8 {
+                            remaining = false;
+                        }
+                    }
+                    if remaining {
+                        top = choice;
+                    }
+                }
+
+                for option in 0..MAX_RANKED_OPTIONS {
+                    if eliminated & (1 << option) == 0 {
+                        if top == option as u8 {
+                            counts[slot] += weight;
+                        }
+                        slot += 1;
+                    }
+                }
+            }
+        }
+
+        ranked_ctxt.owner.from_arcis(Pack::new(counts))
+    }
+
+    /// Runs the instant-runoff count and reveals the winner.
+    ///
+    /// Each round counts every ballot for its most preferred remaining option. An option
+    /// with a strict majority of those ballots wins. Otherwise the option with the fewest
+    /// is eliminated, the highest index on a tie, until two remain and the one with more
+    /// ballots wins. Which options were eliminated stays secret unless the round counts
+    /// are disclosed.
+    ///
+    /// # Arguments
+    /// * `ranked_ctxt` - Encrypted counters of the poll
+    /// * `num_options` - Number of options the poll offers
+    /// * `disclose_rounds` - Whether to reveal each round's counts as well
+    ///
+    /// # Returns
+    /// * Index of the winning option, the lowest index if the final two are tied
+    /// * `true` if the final two are tied
+    /// * Each round's count per option, zero if not disclosed or once the count is over
+    #[instruction]
+    pub fn reveal_irv_winner(
+        ranked_ctxt: Enc<Mxe, RankedCounts>,
+        num_options: u8,
+        disclose_rounds: bool,
+    ) -> (u8, bool, [[u64; MAX_RANKED_OPTIONS]; IRV_ROUNDS]) {
+        let counts = ranked_ctxt.to_arcis().unpack();
+
+        let mut eliminated = [false; MAX_RANKED_OPTIONS];
+        for option in 0..MAX_RANKED_OPTIONS {
+            eliminated[option] = option as u8 >= num_options;
+        }
+
+        let mut decided = false;
+        let mut winner: u8 = 0;
+        let mut tie = false;
+        let mut rounds = [[0; MAX_RANKED_OPTIONS]; IRV_ROUNDS];
+        for round in 0..IRV_ROUNDS {
+            // Pick out the counters for the options eliminated so far
+            let mut round_counts = [0; MAX_RANKED_OPTIONS];
+            let mut slot = 0;
+            for set in 0..1 << MAX_RANKED_OPTIONS {
+                if eliminated_count(set) <= IRV_ROUNDS - 1 {
+                    let mut current = true;
+                    for option in 0..MAX_RANKED_OPTIONS {
+                        if eliminated[option] != (set & (1 << option) != 0) {
+                            current = false;
+                        }
+                    }
+                    for option in 0..MAX_RANKED_OPTIONS {
+                        if set & (1 << option) == 0 {
+                            if current {
+                                round_counts[option] = counts[slot];
+                            }
+                            slot += 1;
+                        }
+                    }
+                }
+            }
+
+            let mut total: u64 = 0;
+            let mut remaining: u8 = 0;
+            let mut leader: u8 = 0;
+            let mut most: u64 = 0;
+            let mut loser: u8 = 0;
+            let mut fewest: u64 = 0;
+            for option in 0..MAX_RANKED_OPTIONS {
+                if !eliminated[option] {
+                    let count = round_counts[option];
+                    if remaining == 0 || count > most {
+                        leader = option as u8;
+                        most = count;
+                    }
+                    if remaining == 0 || count <= fewest {
+                        loser = option as u8;
+                        fewest = count;
+                    }
+                    total += count;
+                    remaining += 1;
+                }
+            }
+
+            if !decided {
+                rounds[round] = round_counts;
+                if most * 2 > total || remaining <= 2 {
+                    decided = true;
+                    winner = leader;
+                    // With two options left, only a dead heat leaves the leader short of a majority
+                    tie = most * 2 <= total;
+                } else {
+                    for option in 0..MAX_RANKED_OPTIONS {
+                        if loser == option as u8 {
+                            eliminated[option] = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut revealed_rounds = [[0; MAX_RANKED_OPTIONS]; IRV_ROUNDS];
+        if disclose_rounds {
+            for round in 0..IRV_ROUNDS {
+                for option in 0..MAX_RANKED_OPTIONS {
+                    revealed_rounds[round][option] = rounds[round][option].reveal();
+                }
+            }
+        }
+        (winner.reveal(), tie.reveal(), revealed_rounds)
+    }
+
+    /// Number of options in a set of eliminated options, given as a bitmask.
+    fn eliminated_count(set: usize) -> usize {
+        let mut count = 0;
+        for option in 0..MAX_RANKED_OPTIONS {
+            if set & (1 << option) != 0 {
+                count += 1;
+            }
+        }
+        count
+    }
+
     /// Reveals the final result of the poll by comparing vote tallies.
     ///
-    /// Decrypts the vote counters and determines whether the majority voted yes or no.
-    /// Only the final result (majority decision) is revealed, not the actual vote counts.
+    /// Decrypts the vote counters and finds the option with the most votes.
+    /// Only the winning option is revealed, not the actual vote counts.
     ///
     /// # Arguments
     /// * `vote_stats_ctxt` - Encrypted vote tallies to be revealed
     ///
     /// # Returns
-    /// * `true` if more people voted yes than no
-    /// * `false` if more people voted no than yes (or tie)
+    /// * Index of the option with the most votes, the lowest such index on a tie
+    /// * `true` if another option has as many votes
     #[instruction]
-    pub fn reveal_result(vote_stats_ctxt: Enc<Mxe, VoteStats>) -> bool {
+    pub fn reveal_result(vote_stats_ctxt: Enc<Mxe, VoteStats>) -> (u8, bool) {
         let vote_stats = vote_stats_ctxt.to_arcis();
-        (vote_stats.yes > vote_stats.no).reveal()
+
+        let mut winner: u8 = 0;
+        let mut most_votes = vote_stats.counts[0];
+        let mut tie = false;
+        for i in 1..MAX_OPTIONS {
+            if vote_stats.counts[i] > most_votes {
+                most_votes = vote_stats.counts[i];
+                winner = i as u8;
+                tie = false;
+            } else if vote_stats.counts[i] == most_votes {
+                tie = true;
+            }
+        }
+        (winner.reveal(), tie.reveal())
+    }
+
+    /// Reveals the exact vote count of every option.
+    ///
+    /// For polls created with full disclosure. Individual votes stay private, but
+    /// every counter is decrypted, including the count of invalid ballots.
+    ///
+    /// # Arguments
+    /// * `vote_stats_ctxt` - Encrypted vote tallies to be revealed
+    ///
+    /// # Returns
+    /// The vote count of each option slot and the number of invalid ballots
+    #[instruction]
+    pub fn reveal_tally(vote_stats_ctxt: Enc<Mxe, VoteStats>) -> ([u64; MAX_OPTIONS], u64) {
+        let vote_stats = vote_stats_ctxt.to_arcis();
+
+        let mut counts = [0; MAX_OPTIONS];
+        for i in 0..MAX_OPTIONS {
+            counts[i] = vote_stats.counts[i].reveal();
+        }
+        (counts, vote_stats.invalid.reveal())
     }
 }
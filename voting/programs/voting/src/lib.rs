@@ -1,10 +1,38 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_spl::token_interface::{
+    self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
 use arcium_anchor::prelude::*;
 use arcium_client::idl::arcium::types::CallbackAccount;
 
 const COMP_DEF_OFFSET_INIT_VOTE_STATS: u32 = comp_def_offset("init_vote_stats");
 const COMP_DEF_OFFSET_VOTE: u32 = comp_def_offset("vote");
+const COMP_DEF_OFFSET_VOTE_QUADRATIC: u32 = comp_def_offset("vote_quadratic");
+const COMP_DEF_OFFSET_CHANGE_VOTE: u32 = comp_def_offset("change_vote");
 const COMP_DEF_OFFSET_REVEAL: u32 = comp_def_offset("reveal_result");
+const COMP_DEF_OFFSET_REVEAL_TALLY: u32 = comp_def_offset("reveal_tally");
+const COMP_DEF_OFFSET_INIT_RANKED_STATS: u32 = comp_def_offset("init_ranked_stats");
+const COMP_DEF_OFFSET_VOTE_RANKED: u32 = comp_def_offset("vote_ranked");
+const COMP_DEF_OFFSET_REVEAL_IRV_WINNER: u32 = comp_def_offset("reveal_irv_winner");
+
+/// Most options a poll can offer
+const MAX_OPTIONS: usize = 8;
+/// Longest option label, in bytes
+const MAX_OPTION_LEN: usize = 32;
+/// Encrypted counters kept per poll: one per option, plus one for invalid ballots
+const TALLY_SLOTS: usize = MAX_OPTIONS + 1;
+/// Most options a ranked-choice poll can offer
+const MAX_RANKED_OPTIONS: usize = 4;
+/// Elimination rounds of an instant-runoff count, from every option down to the final two
+const IRV_ROUNDS: usize = MAX_RANKED_OPTIONS - 1;
+/// Encrypted chunks of a ranked-choice poll's counters: 28 packed u64s, three per field element
+const RANKED_CHUNKS: usize = 10;
+/// Deepest eligibility proof accepted, enough for an electorate of 2^24 voters
+const MAX_PROOF_DEPTH: usize = 24;
+/// Domain separators for the eligibility Merkle tree, so a leaf can't pass as an inner node
+const MERKLE_LEAF_PREFIX: u8 = 0;
+const MERKLE_NODE_PREFIX: u8 = 1;
 
 declare_id!("J7KTdhMTVhy7vtgyFSXi9SpptdTDmpg93pB53UdfuttF");
 
@@ -17,7 +45,7 @@ pub mod voting {
         Ok(())
     }
 
-    /// Creates a new confidential poll with the given question.
+    /// Creates a new confidential poll with the given question and options.
     ///
     /// This initializes a poll account and sets up the encrypted vote counters using MPC.
     /// The vote tallies are stored in encrypted form and can only be revealed by the poll authority.
@@ -26,26 +54,83 @@ pub mod voting {
     /// # Arguments
     /// * `id` - Unique identifier for this poll
     /// * `question` - The poll question voters will respond to
+    /// * `options` - The answers voters choose from (2 to 8, up to 32 bytes each)
+    /// * `disclosure` - How much of the tally the authority may reveal
+    /// * `starts_at` - Unix timestamp from which votes are accepted
+    /// * `ends_at` - Unix timestamp after which votes are no longer accepted
+    /// * `weight_mint` - Token whose locked amount sets each vote's weight, or `None` for one
+    ///   vote per voter
+    /// * `eligibility_root` - Merkle root of the eligible voters, or `None` to let anyone vote
+    /// * `method` - How voters fill in their ballot
+    /// * `allow_vote_changes` - Whether voters may replace their vote with `change_vote` while
+    ///   the poll is open. Single-choice polls only.
     pub fn create_new_poll(
         ctx: Context<CreateNewPoll>,
         computation_offset: u64,
         id: u32,
         question: String,
+        options: Vec<String>,
+        disclosure: Disclosure,
+        starts_at: i64,
+        ends_at: i64,
+        weight_mint: Option<Pubkey>,
+        eligibility_root: Option<[u8; 32]>,
+        method: VotingMethod,
+        allow_vote_changes: bool,
     ) -> Result<()> {
         msg!("Creating a new poll");
 
+        require!(
+            starts_at < ends_at && ends_at > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidVotingWindow
+        );
+        require!(
+            options.len() >= 2 && options.len() <= MAX_OPTIONS,
+            ErrorCode::InvalidOptionCount
+        );
+        require!(
+            options.iter().all(|option| option.len() <= MAX_OPTION_LEN),
+            ErrorCode::OptionTooLong
+        );
+        match method {
+            VotingMethod::SingleChoice => {}
+            VotingMethod::Quadratic { credits } => {
+                require!(credits > 0, ErrorCode::InvalidCreditBudget);
+            }
+            VotingMethod::RankedChoice => {
+                require!(
+                    options.len() <= MAX_RANKED_OPTIONS,
+                    ErrorCode::TooManyRankedOptions
+                );
+            }
+        }
+        require!(
+            !allow_vote_changes || method == VotingMethod::SingleChoice,
+            ErrorCode::VoteChangesUnsupported
+        );
+
         // Initialize the poll account with the provided parameters
         ctx.accounts.poll_acc.question = question;
         ctx.accounts.poll_acc.bump = ctx.bumps.poll_acc;
         ctx.accounts.poll_acc.id = id;
         ctx.accounts.poll_acc.authority = ctx.accounts.payer.key();
-        ctx.accounts.poll_acc.vote_state = [[0; 32]; 2];
+        ctx.accounts.poll_acc.vote_state = [[0; 32]; TALLY_SLOTS];
+        ctx.accounts.poll_acc.options = options;
+        ctx.accounts.poll_acc.disclosure = disclosure;
+        ctx.accounts.poll_acc.starts_at = starts_at;
+        ctx.accounts.poll_acc.ends_at = ends_at;
+        ctx.accounts.poll_acc.status = PollStatus::Open;
+        ctx.accounts.poll_acc.weight_mint = weight_mint;
+        ctx.accounts.poll_acc.eligibility_root = eligibility_root;
+        ctx.accounts.poll_acc.method = method;
+        ctx.accounts.poll_acc.allow_vote_changes = allow_vote_changes;
+        ctx.accounts.poll_acc.total_weight = 0;
 
         let args = ArgBuilder::new().build();
 
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
-        // Initialize encrypted vote counters (yes/no) through MPC
+        // Initialize encrypted vote counters (one per option) through MPC
         queue_computation(
             ctx.accounts,
             computation_offset,
@@ -91,39 +176,104 @@ pub mod voting {
 
     /// Submits an encrypted vote to the poll.
     ///
-    /// This function allows a voter to cast their vote (yes/no) in encrypted form.
+    /// This function allows a voter to cast their vote in encrypted form.
     /// The vote is added to the running tally through MPC computation, ensuring
     /// that individual votes remain confidential while updating the overall count.
+    /// In a token-weighted poll the vote counts with the tokens the voter locked in the
+    /// poll with `lock_tokens`, read from `voter_escrow`. The weight is public, the chosen
+    /// option is not.
+    ///
+    /// A delegate can cast the votes delegated to them along with their own by passing,
+    /// in the remaining accounts, each delegator's `Delegation` and `VoterRecord` PDA, plus
+    /// their token escrow in a token-weighted poll. The vote then counts with the combined
+    /// weight, and each delegator's `VoterRecord` is created so they can't be counted twice.
+    /// If the computation aborts, those records and the voter's are closed again so everyone
+    /// can vote afresh. A delegator whose vote was already cast this way is turned away with
+    /// `VoteDelegated` and overrides it with `change_vote` instead.
     ///
     /// # Arguments
-    /// * `vote` - Encrypted vote (true for yes, false for no)
+    /// * `vote` - Encrypted index of the chosen option
     /// * `vote_encryption_pubkey` - Voter's public key for encryption
     /// * `vote_nonce` - Cryptographic nonce for the vote encryption
-    pub fn vote(
-        ctx: Context<Vote>,
+    /// * `eligibility_proof` - Merkle proof that the voter is in the poll's electorate,
+    ///   empty for polls open to everyone
+    pub fn vote<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Vote<'info>>,
         computation_offset: u64,
         _id: u32,
         vote: [u8; 32],
         vote_encryption_pubkey: [u8; 32],
         vote_nonce: u128,
+        eligibility_proof: Vec<[u8; 32]>,
     ) -> Result<()> {
+        let poll = &ctx.accounts.poll_acc;
+        poll.require_voting_open()?;
+        // A fresh record is zeroed; one already in use holds its bump
+        let voter_record = &ctx.accounts.voter_record;
+        require!(
+            voter_record.delegated_to.is_none(),
+            ErrorCode::VoteDelegated
+        );
+        require!(voter_record.bump == 0, ErrorCode::AlreadyVoted);
+        require!(
+            poll.method == VotingMethod::SingleChoice,
+            ErrorCode::WrongBallotType
+        );
+        require!(
+            poll.is_eligible(&ctx.accounts.payer.key(), &eligibility_proof),
+            ErrorCode::NotEligible
+        );
+        let own_weight = poll.ballot_weight(ctx.accounts.voter_escrow.as_deref())?;
+        let (delegated_weight, delegator_records) =
+            ctx.accounts.count_delegations(ctx.remaining_accounts)?;
+        let weight = own_weight
+            .checked_add(delegated_weight)
+            .ok_or(ErrorCode::WeightLimitReached)?;
+        ctx.accounts.poll_acc.add_weight(weight)?;
+
         let args = ArgBuilder::new()
             .x25519_pubkey(vote_encryption_pubkey)
             .plaintext_u128(vote_nonce)
-            .encrypted_bool(vote)
+            .encrypted_u8(vote)
             .plaintext_u128(ctx.accounts.poll_acc.nonce)
             .account(
                 ctx.accounts.poll_acc.key(),
                 // Offset calculation: 8 bytes (discriminator) + 1 byte (bump)
                 8 + 1,
-                32 * 2, // 2 vote counters (yes/no), each stored as 32-byte ciphertext
+                // One counter per option slot plus the invalid counter, each a 32-byte ciphertext
+                32 * TALLY_SLOTS as u32,
             )
+            .plaintext_u8(ctx.accounts.poll_acc.options.len() as u8)
+            .plaintext_u64(weight)
             .build();
 
         ctx.accounts.voter_record.bump = ctx.bumps.voter_record;
+        ctx.accounts.voter_record.weight = weight;
+        ctx.accounts.voter_record.delegated_weight = delegated_weight;
+        ctx.accounts.voter_record.pending = true;
 
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
+        let mut callback_accounts = vec![
+            CallbackAccount {
+                pubkey: ctx.accounts.poll_acc.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.voter_record.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.payer.key(),
+                is_writable: true,
+            },
+        ];
+        // The delegators' records follow, to be closed again if the computation aborts
+        callback_accounts.extend(delegator_records.into_iter().map(|pubkey| CallbackAccount {
+            pubkey,
+            is_writable: true,
+        }));
+
         queue_computation(
             ctx.accounts,
             computation_offset,
@@ -131,10 +281,7 @@ pub mod voting {
             vec![VoteCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[CallbackAccount {
-                    pubkey: ctx.accounts.poll_acc.key(),
-                    is_writable: true,
-                }],
+                &callback_accounts,
             )?],
             1,
             0,
@@ -147,16 +294,36 @@ pub mod voting {
         ctx: Context<VoteCallback>,
         output: SignedComputationOutputs<VoteOutput>,
     ) -> Result<()> {
-        let o = match output.verify_output(
+        let (vote_stats, ballot) = match output.verify_output(
             &ctx.accounts.cluster_account,
             &ctx.accounts.computation_account,
         ) {
-            Ok(VoteOutput { field_0 }) => field_0,
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+            Ok(VoteOutput {
+                field_0:
+                    VoteOutputStruct0 {
+                        field_0: vote_stats,
+                        field_1: ballot,
+                    },
+            }) => (vote_stats, ballot),
+            Err(_) => {
+                ctx.accounts.discard_vote(ctx.remaining_accounts)?;
+                emit!(VoteAbortedEvent {
+                    voter: ctx.accounts.payer.key(),
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+                return Ok(());
+            }
         };
 
-        ctx.accounts.poll_acc.vote_state = o.ciphertexts;
-        ctx.accounts.poll_acc.nonce = o.nonce;
+        // A vote that lands after the result was revealed can't change the finalized tally
+        if ctx.accounts.poll_acc.status == PollStatus::Finalized {
+            return Ok(());
+        }
+        ctx.accounts.poll_acc.vote_state = vote_stats.ciphertexts;
+        ctx.accounts.poll_acc.nonce = vote_stats.nonce;
+        ctx.accounts
+            .voter_record
+            .record_ballot(ballot.ciphertexts[0], ballot.nonce);
 
         let clock = Clock::get()?;
 
@@ -167,6 +334,462 @@ pub mod voting {
         Ok(())
     }
 
+    pub fn init_change_vote_comp_def(ctx: Context<InitChangeVoteCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Replaces the voter's earlier vote with a new encrypted one.
+    ///
+    /// Only for polls created with `allow_vote_changes`, while they are still open. The MPC
+    /// computation takes the vote recorded in the voter's `VoterRecord` back out of the tally,
+    /// adds the new one and records it in its place. Nobody learns which option was dropped
+    /// or picked. In a token-weighted poll the new vote counts with the tokens the voter has
+    /// locked.
+    ///
+    /// A delegator whose vote a delegate already cast uses this to vote directly, in any
+    /// single-choice poll. Their weight is taken back out of the delegate's recorded vote,
+    /// passed as `delegate_record`, and their own vote is counted instead. The delegate's
+    /// record is held pending until then, and its weights only move once the override lands.
+    ///
+    /// # Arguments
+    /// * `vote` - Encrypted index of the newly chosen option
+    /// * `vote_encryption_pubkey` - Voter's public key for encryption
+    /// * `vote_nonce` - Cryptographic nonce for the vote encryption
+    pub fn change_vote(
+        ctx: Context<ChangeVote>,
+        computation_offset: u64,
+        _id: u32,
+        vote: [u8; 32],
+        vote_encryption_pubkey: [u8; 32],
+        vote_nonce: u128,
+    ) -> Result<()> {
+        let poll = &ctx.accounts.poll_acc;
+        poll.require_voting_open()?;
+        let overriding_delegate = ctx.accounts.voter_record.delegated_to.is_some();
+        require!(
+            poll.allow_vote_changes || overriding_delegate,
+            ErrorCode::VoteChangesNotAllowed
+        );
+        require!(
+            poll.method == VotingMethod::SingleChoice,
+            ErrorCode::WrongBallotType
+        );
+        let weight = poll
+            .ballot_weight(ctx.accounts.voter_escrow.as_deref())?
+            .checked_add(ctx.accounts.voter_record.delegated_weight)
+            .ok_or(ErrorCode::WeightLimitReached)?;
+
+        // The vote to take back out: the delegate's if overriding a delegation, else the voter's
+        let recorded = if overriding_delegate {
+            ctx.accounts
+                .delegate_record
+                .as_deref()
+                .ok_or(ErrorCode::MissingDelegateRecord)?
+        } else {
+            &*ctx.accounts.voter_record
+        };
+        // The recorded ballot is only valid once the previous vote or change has landed
+        require!(!recorded.pending, ErrorCode::BallotPending);
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(vote_encryption_pubkey)
+            .plaintext_u128(vote_nonce)
+            .encrypted_u8(vote)
+            .plaintext_u128(recorded.ballot_nonce)
+            .account(
+                recorded.key(),
+                // Offset calculation: 8 bytes (discriminator) + 1 byte (bump)
+                8 + 1,
+                32, // The recorded ballot, a single 32-byte ciphertext
+            )
+            .plaintext_u128(ctx.accounts.poll_acc.nonce)
+            .account(
+                ctx.accounts.poll_acc.key(),
+                // Offset calculation: 8 bytes (discriminator) + 1 byte (bump)
+                8 + 1,
+                // One counter per option slot plus the invalid counter, each a 32-byte ciphertext
+                32 * TALLY_SLOTS as u32,
+            )
+            .plaintext_u8(ctx.accounts.poll_acc.options.len() as u8)
+            .plaintext_u64(ctx.accounts.voter_record.weight)
+            .plaintext_u64(weight)
+            .build();
+
+        if overriding_delegate {
+            ctx.accounts
+                .delegate_record
+                .as_deref_mut()
+                .ok_or(ErrorCode::MissingDelegateRecord)?
+                .pending = true;
+        }
+        // The recorded vote's weight comes back out of the total before the new one goes in
+        let poll = &mut ctx.accounts.poll_acc;
+        poll.total_weight = poll
+            .total_weight
+            .saturating_sub(ctx.accounts.voter_record.weight);
+        poll.add_weight(weight)?;
+        let voter_record = &mut ctx.accounts.voter_record;
+        voter_record.previous_weight = voter_record.weight;
+        voter_record.weight = weight;
+        voter_record.pending = true;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let delegate_record = ctx
+            .accounts
+            .delegate_record
+            .as_ref()
+            .map(|record| record.key());
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![ChangeVoteCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.poll_acc.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.voter_record.key(),
+                        is_writable: true,
+                    },
+                    // The program id stands in for an absent delegate record
+                    CallbackAccount {
+                        pubkey: delegate_record.unwrap_or(crate::ID),
+                        is_writable: delegate_record.is_some(),
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "change_vote")]
+    pub fn change_vote_callback(
+        ctx: Context<ChangeVoteCallback>,
+        output: SignedComputationOutputs<ChangeVoteOutput>,
+    ) -> Result<()> {
+        let (vote_stats, ballot) = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(ChangeVoteOutput {
+                field_0:
+                    ChangeVoteOutputStruct0 {
+                        field_0: vote_stats,
+                        field_1: ballot,
+                    },
+            }) => (vote_stats, ballot),
+            Err(_) => {
+                ctx.accounts.restore_ballot();
+                emit!(VoteChangeAbortedEvent {
+                    voter_record: ctx.accounts.voter_record.key(),
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+                return Ok(());
+            }
+        };
+
+        if ctx.accounts.poll_acc.status == PollStatus::Finalized {
+            return Ok(());
+        }
+        ctx.accounts.poll_acc.vote_state = vote_stats.ciphertexts;
+        ctx.accounts.poll_acc.nonce = vote_stats.nonce;
+        ctx.accounts
+            .voter_record
+            .record_ballot(ballot.ciphertexts[0], ballot.nonce);
+        // An override moves the delegator's weight out of the delegate's record now that the
+        // tally no longer counts it there
+        if let Some(delegate_record) = ctx.accounts.delegate_record.as_mut() {
+            let withdrawn = ctx.accounts.voter_record.previous_weight;
+            delegate_record.weight = delegate_record
+                .weight
+                .checked_sub(withdrawn)
+                .ok_or(ErrorCode::InvalidDelegation)?;
+            delegate_record.delegated_weight = delegate_record
+                .delegated_weight
+                .checked_sub(withdrawn)
+                .ok_or(ErrorCode::InvalidDelegation)?;
+            delegate_record.pending = false;
+            ctx.accounts.voter_record.delegated_to = None;
+        }
+
+        emit!(VoteChangedEvent {
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn init_vote_quadratic_comp_def(ctx: Context<InitVoteQuadraticCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Submits an encrypted quadratic-voting ballot to a quadratic poll.
+    ///
+    /// The ballot gives each option an encrypted number of votes. The MPC computation checks
+    /// that the squares of the votes add up to no more than the poll's credit budget and
+    /// that only the poll's options got votes, then adds them to the running tally. A ballot
+    /// failing either check is counted as invalid, without revealing which check failed or
+    /// what it contained.
+    ///
+    /// # Arguments
+    /// * `votes` - Encrypted number of votes for each option slot
+    /// * `vote_encryption_pubkey` - Voter's public key for encryption
+    /// * `vote_nonce` - Cryptographic nonce for the ballot encryption
+    /// * `eligibility_proof` - Merkle proof that the voter is in the poll's electorate,
+    ///   empty for polls open to everyone
+    pub fn vote_quadratic(
+        ctx: Context<VoteQuadratic>,
+        computation_offset: u64,
+        _id: u32,
+        votes: [[u8; 32]; MAX_OPTIONS],
+        vote_encryption_pubkey: [u8; 32],
+        vote_nonce: u128,
+        eligibility_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let poll = &ctx.accounts.poll_acc;
+        poll.require_voting_open()?;
+        let VotingMethod::Quadratic { credits } = poll.method else {
+            return Err(ErrorCode::WrongBallotType.into());
+        };
+        require!(
+            poll.is_eligible(&ctx.accounts.payer.key(), &eligibility_proof),
+            ErrorCode::NotEligible
+        );
+        let weight = poll.ballot_weight(ctx.accounts.voter_escrow.as_deref())?;
+        ctx.accounts.poll_acc.add_weight(weight)?;
+
+        let mut args = ArgBuilder::new()
+            .x25519_pubkey(vote_encryption_pubkey)
+            .plaintext_u128(vote_nonce);
+        for option_votes in votes {
+            args = args.encrypted_u8(option_votes);
+        }
+        let args = args
+            .plaintext_u128(ctx.accounts.poll_acc.nonce)
+            .account(
+                ctx.accounts.poll_acc.key(),
+                // Offset calculation: 8 bytes (discriminator) + 1 byte (bump)
+                8 + 1,
+                // One counter per option slot plus the invalid counter, each a 32-byte ciphertext
+                32 * TALLY_SLOTS as u32,
+            )
+            .plaintext_u8(ctx.accounts.poll_acc.options.len() as u8)
+            .plaintext_u64(credits)
+            .plaintext_u64(weight)
+            .build();
+
+        ctx.accounts.voter_record.bump = ctx.bumps.voter_record;
+        ctx.accounts.voter_record.weight = weight;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![VoteQuadraticCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.poll_acc.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.voter_record.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.payer.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "vote_quadratic")]
+    pub fn vote_quadratic_callback(
+        ctx: Context<VoteQuadraticCallback>,
+        output: SignedComputationOutputs<VoteQuadraticOutput>,
+    ) -> Result<()> {
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(VoteQuadraticOutput { field_0 }) => field_0,
+            Err(_) => {
+                let accounts = &mut ctx.accounts;
+                accounts
+                    .poll_acc
+                    .discard_ballot(&accounts.voter_record, &accounts.payer)?;
+                emit!(VoteAbortedEvent {
+                    voter: accounts.payer.key(),
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+                return Ok(());
+            }
+        };
+
+        if ctx.accounts.poll_acc.status == PollStatus::Finalized {
+            return Ok(());
+        }
+        ctx.accounts.poll_acc.vote_state = o.ciphertexts;
+        ctx.accounts.poll_acc.nonce = o.nonce;
+
+        emit!(VoteEvent {
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Delegates the caller's vote to `delegate`.
+    ///
+    /// The delegation covers one poll, when `scope` is a poll account, or every poll of an
+    /// authority, when `scope` is that authority. The delegate casts it by passing it to `vote`.
+    /// Voting directly still works: before the delegate votes the delegation is simply not
+    /// counted, and afterwards `change_vote` overrides it.
+    ///
+    /// # Arguments
+    /// * `scope` - Poll account or poll authority the delegation applies to
+    /// * `delegate` - Who votes on the caller's behalf
+    pub fn delegate_vote(
+        ctx: Context<DelegateVote>,
+        scope: Pubkey,
+        delegate: Pubkey,
+    ) -> Result<()> {
+        require_keys_neq!(
+            delegate,
+            ctx.accounts.delegator.key(),
+            ErrorCode::InvalidDelegation
+        );
+
+        let delegation = &mut ctx.accounts.delegation;
+        delegation.bump = ctx.bumps.delegation;
+        delegation.delegator = ctx.accounts.delegator.key();
+        delegation.delegate = delegate;
+        delegation.scope = scope;
+
+        emit!(DelegationEvent {
+            delegator: delegation.delegator,
+            delegate,
+            scope,
+        });
+        Ok(())
+    }
+
+    /// Withdraws a delegation so it can't be cast any more.
+    ///
+    /// Votes a delegate already cast with it stay counted, and the delegator can still
+    /// override them with `change_vote`.
+    ///
+    /// # Arguments
+    /// * `scope` - Poll account or poll authority the delegation applies to
+    pub fn revoke_delegation(_ctx: Context<RevokeDelegation>, scope: Pubkey) -> Result<()> {
+        msg!("Revoking delegation for scope {}", scope);
+        Ok(())
+    }
+
+    /// Locks the voter's tokens in a token-weighted poll, where they set the weight of their
+    /// ballot.
+    ///
+    /// The tokens move into an escrow owned by the poll (`[b"escrow", poll, voter]`) and only
+    /// come back with `withdraw_tokens` once voting has ended, so the same tokens can't be
+    /// moved to another wallet and counted again. Delegators lock their tokens too, so their
+    /// delegate can cast them.
+    ///
+    /// # Arguments
+    /// * `amount` - Tokens to move from `voter_token_account` into the escrow
+    pub fn lock_tokens(ctx: Context<LockTokens>, _id: u32, amount: u64) -> Result<()> {
+        ctx.accounts.poll_acc.require_voting_open()?;
+        require!(amount > 0, ErrorCode::NoVotingWeight);
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.voter_token_account.to_account_info(),
+                    mint: ctx.accounts.weight_mint.to_account_info(),
+                    to: ctx.accounts.voter_escrow.to_account_info(),
+                    authority: ctx.accounts.voter.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.weight_mint.decimals,
+        )
+    }
+
+    /// Returns the voter's locked tokens once voting has ended and closes their escrow.
+    pub fn withdraw_tokens(ctx: Context<WithdrawTokens>, _id: u32) -> Result<()> {
+        let poll = &ctx.accounts.poll_acc;
+        require!(
+            poll.voting_ended(Clock::get()?.unix_timestamp),
+            ErrorCode::VotingStillOpen
+        );
+
+        // The poll PDA owns every escrow of the poll
+        let id_bytes = poll.id.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"poll",
+            poll.authority.as_ref(),
+            id_bytes.as_ref(),
+            &[poll.bump],
+        ]];
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.voter_escrow.to_account_info(),
+                    mint: ctx.accounts.weight_mint.to_account_info(),
+                    to: ctx.accounts.voter_token_account.to_account_info(),
+                    authority: ctx.accounts.poll_acc.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            ctx.accounts.voter_escrow.amount,
+            ctx.accounts.weight_mint.decimals,
+        )?;
+        token_interface::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.voter_escrow.to_account_info(),
+                destination: ctx.accounts.voter.to_account_info(),
+                authority: ctx.accounts.poll_acc.to_account_info(),
+            },
+            signer_seeds,
+        ))
+    }
+
+    /// Closes a poll to new votes before its window ends, so the result can be revealed.
+    ///
+    /// # Arguments
+    /// * `id` - The poll ID to close
+    pub fn close_poll(ctx: Context<ClosePoll>, id: u32) -> Result<()> {
+        let poll = &mut ctx.accounts.poll_acc;
+        require!(
+            !poll.voting_ended(Clock::get()?.unix_timestamp),
+            ErrorCode::VotingClosed
+        );
+
+        msg!("Closing poll with id {}", id);
+        poll.status = PollStatus::Closed;
+        Ok(())
+    }
+
     pub fn init_reveal_result_comp_def(ctx: Context<InitRevealResultCompDef>) -> Result<()> {
         init_comp_def(ctx.accounts, None, None)?;
         Ok(())
@@ -174,9 +797,10 @@ pub mod voting {
 
     /// Reveals the final result of the poll.
     ///
-    /// Only the poll authority can call this function to decrypt and reveal the vote tallies.
-    /// The MPC computation compares the yes and no vote counts and returns whether
-    /// the majority voted yes (true) or no (false).
+    /// Only the poll authority can call this function to decrypt and reveal the vote tallies,
+    /// once voting has ended. The MPC computation compares the vote counts and returns the
+    /// index of the option with the most votes, and whether another option tied with it.
+    /// The callback stores the result and finalizes the poll.
     ///
     /// # Arguments
     /// * `id` - The poll ID to reveal results for
@@ -189,6 +813,11 @@ pub mod voting {
             ctx.accounts.payer.key() == ctx.accounts.poll_acc.authority,
             ErrorCode::InvalidAuthority
         );
+        require!(
+            ctx.accounts.poll_acc.method != VotingMethod::RankedChoice,
+            ErrorCode::RankedChoiceReveal
+        );
+        ctx.accounts.poll_acc.require_revealable()?;
 
         msg!("Revealing voting result for poll with id {}", id);
 
@@ -198,7 +827,7 @@ pub mod voting {
                 ctx.accounts.poll_acc.key(),
                 // Offset calculation: 8 bytes (discriminator) + 1 byte (bump)
                 8 + 1,
-                32 * 2, // 2 encrypted vote counters (yes/no), 32 bytes each
+                32 * TALLY_SLOTS as u32, // Every encrypted counter, 32 bytes each
             )
             .build();
 
@@ -211,7 +840,10 @@ pub mod voting {
             vec![RevealResultCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[],
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.poll_acc.key(),
+                    is_writable: true,
+                }],
             )?],
             1,
             0,
@@ -219,29 +851,1509 @@ pub mod voting {
         Ok(())
     }
 
-    #[arcium_callback(encrypted_ix = "reveal_result")]
-    pub fn reveal_result_callback(
-        ctx: Context<RevealResultCallback>,
-        output: SignedComputationOutputs<RevealResultOutput>,
-    ) -> Result<()> {
-        let o = match output.verify_output(
-            &ctx.accounts.cluster_account,
-            &ctx.accounts.computation_account,
-        ) {
-            Ok(RevealResultOutput { field_0 }) => field_0,
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
-        };
+    #[arcium_callback(encrypted_ix = "reveal_result")]
+    pub fn reveal_result_callback(
+        ctx: Context<RevealResultCallback>,
+        output: SignedComputationOutputs<RevealResultOutput>,
+    ) -> Result<()> {
+        let (winning_option, tie) = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealResultOutput {
+                field_0:
+                    RevealResultOutputStruct0 {
+                        field_0: winning_option,
+                        field_1: tie,
+                    },
+            }) => (winning_option, tie),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        // A reveal queued alongside another one can't overwrite the final result
+        let poll = &mut ctx.accounts.poll_acc;
+        if poll.status == PollStatus::Finalized {
+            return Ok(());
+        }
+        poll.finalize(PollResult {
+            winning_option,
+            tie,
+        });
+
+        emit!(RevealResultEvent {
+            winning_option,
+            tie
+        });
+
+        Ok(())
+    }
+
+    pub fn init_reveal_tally_comp_def(ctx: Context<InitRevealTallyCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Reveals the exact vote counts of the poll.
+    ///
+    /// Only the poll authority can call this, once voting has ended, and only for polls
+    /// created with `Disclosure::FullTally`. The callback stores the counts, turnout and
+    /// outcome in the poll account and finalizes the poll.
+    ///
+    /// # Arguments
+    /// * `id` - The poll ID to reveal the tally for
+    pub fn reveal_tally(ctx: Context<RevealTally>, computation_offset: u64, id: u32) -> Result<()> {
+        require!(
+            ctx.accounts.payer.key() == ctx.accounts.poll_acc.authority,
+            ErrorCode::InvalidAuthority
+        );
+        require!(
+            ctx.accounts.poll_acc.disclosure == Disclosure::FullTally,
+            ErrorCode::TallyNotDisclosable
+        );
+        require!(
+            ctx.accounts.poll_acc.method != VotingMethod::RankedChoice,
+            ErrorCode::RankedChoiceReveal
+        );
+        ctx.accounts.poll_acc.require_revealable()?;
+
+        msg!("Revealing vote tally for poll with id {}", id);
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(ctx.accounts.poll_acc.nonce)
+            .account(
+                ctx.accounts.poll_acc.key(),
+                // Offset calculation: 8 bytes (discriminator) + 1 byte (bump)
+                8 + 1,
+                32 * TALLY_SLOTS as u32, // Every encrypted counter, 32 bytes each
+            )
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RevealTallyCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.poll_acc.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "reveal_tally")]
+    pub fn reveal_tally_callback(
+        ctx: Context<RevealTallyCallback>,
+        output: SignedComputationOutputs<RevealTallyOutput>,
+    ) -> Result<()> {
+        let (counts, invalid_votes) = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealTallyOutput {
+                field_0:
+                    RevealTallyOutputStruct0 {
+                        field_0: counts,
+                        field_1: invalid_votes,
+                    },
+            }) => (counts, invalid_votes),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let poll = &mut ctx.accounts.poll_acc;
+        if poll.status == PollStatus::Finalized {
+            return Ok(());
+        }
+        let tally = RevealedTally::new(counts, invalid_votes, poll.options.len());
+        poll.tally = Some(tally);
+        poll.finalize(PollResult {
+            winning_option: tally.winning_option,
+            tie: tally.tie,
+        });
+
+        emit!(RevealTallyEvent {
+            id: poll.id,
+            counts: tally.counts,
+            invalid_votes: tally.invalid_votes,
+            turnout: tally.turnout,
+            winning_option: tally.winning_option,
+            tie: tally.tie,
+        });
+
+        Ok(())
+    }
+
+    pub fn init_ranked_stats_comp_def(ctx: Context<InitRankedStatsCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Sets up the encrypted counters of a ranked-choice poll.
+    ///
+    /// Ranked ballots are counted in their own account rather than the poll's tally, so the
+    /// authority calls this once after creating a `VotingMethod::RankedChoice` poll and before
+    /// voting starts.
+    ///
+    /// # Arguments
+    /// * `id` - The ranked-choice poll to set up
+    pub fn init_ranked_tally(
+        ctx: Context<InitRankedTally>,
+        computation_offset: u64,
+        id: u32,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.poll_acc.method == VotingMethod::RankedChoice,
+            ErrorCode::WrongBallotType
+        );
+
+        msg!("Setting up ranked-choice counters for poll with id {}", id);
+        ctx.accounts.ranked_tally.bump = ctx.bumps.ranked_tally;
+
+        let args = ArgBuilder::new().build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![InitRankedStatsCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.ranked_tally.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "init_ranked_stats")]
+    pub fn init_ranked_stats_callback(
+        ctx: Context<InitRankedStatsCallback>,
+        output: SignedComputationOutputs<InitRankedStatsOutput>,
+    ) -> Result<()> {
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(InitRankedStatsOutput { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        ctx.accounts.ranked_tally.state = o.ciphertexts;
+        ctx.accounts.ranked_tally.nonce = o.nonce;
+
+        Ok(())
+    }
+
+    pub fn init_vote_ranked_comp_def(ctx: Context<InitVoteRankedCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Submits an encrypted ranking to a ranked-choice poll.
+    ///
+    /// The ballot lists up to 4 option indices from most to least preferred. Positions can
+    /// be left unused by giving them an index past the poll's options. The MPC computation
+    /// folds the ranking into the poll's instant-runoff counters without revealing it.
+    ///
+    /// # Arguments
+    /// * `ranking` - Encrypted option index at each preference position
+    /// * `vote_encryption_pubkey` - Voter's public key for encryption
+    /// * `vote_nonce` - Cryptographic nonce for the ballot encryption
+    /// * `eligibility_proof` - Merkle proof that the voter is in the poll's electorate,
+    ///   empty for polls open to everyone
+    pub fn vote_ranked(
+        ctx: Context<VoteRanked>,
+        computation_offset: u64,
+        _id: u32,
+        ranking: [[u8; 32]; MAX_RANKED_OPTIONS],
+        vote_encryption_pubkey: [u8; 32],
+        vote_nonce: u128,
+        eligibility_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let poll = &ctx.accounts.poll_acc;
+        poll.require_voting_open()?;
+        require!(
+            poll.method == VotingMethod::RankedChoice,
+            ErrorCode::WrongBallotType
+        );
+        require!(
+            poll.is_eligible(&ctx.accounts.payer.key(), &eligibility_proof),
+            ErrorCode::NotEligible
+        );
+        let weight = poll.ballot_weight(ctx.accounts.voter_escrow.as_deref())?;
+        ctx.accounts.poll_acc.add_weight(weight)?;
+
+        let mut args = ArgBuilder::new()
+            .x25519_pubkey(vote_encryption_pubkey)
+            .plaintext_u128(vote_nonce);
+        for choice in ranking {
+            args = args.encrypted_u8(choice);
+        }
+        let args = args
+            .plaintext_u128(ctx.accounts.ranked_tally.nonce)
+            .account(
+                ctx.accounts.ranked_tally.key(),
+                // Offset calculation: 8 bytes (discriminator) + 1 byte (bump)
+                8 + 1,
+                32 * RANKED_CHUNKS as u32, // Every packed counter chunk, 32 bytes each
+            )
+            .plaintext_u8(ctx.accounts.poll_acc.options.len() as u8)
+            .plaintext_u64(weight)
+            .build();
+
+        ctx.accounts.voter_record.bump = ctx.bumps.voter_record;
+        ctx.accounts.voter_record.weight = weight;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![VoteRankedCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.ranked_tally.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.poll_acc.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.voter_record.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.payer.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "vote_ranked")]
+    pub fn vote_ranked_callback(
+        ctx: Context<VoteRankedCallback>,
+        output: SignedComputationOutputs<VoteRankedOutput>,
+    ) -> Result<()> {
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(VoteRankedOutput { field_0 }) => field_0,
+            Err(_) => {
+                let accounts = &mut ctx.accounts;
+                accounts
+                    .poll_acc
+                    .discard_ballot(&accounts.voter_record, &accounts.payer)?;
+                emit!(VoteAbortedEvent {
+                    voter: accounts.payer.key(),
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+                return Ok(());
+            }
+        };
+
+        // A ballot that lands after the winner was revealed can't change the finalized count
+        if ctx.accounts.poll_acc.status == PollStatus::Finalized {
+            return Ok(());
+        }
+        ctx.accounts.ranked_tally.state = o.ciphertexts;
+        ctx.accounts.ranked_tally.nonce = o.nonce;
+
+        emit!(VoteEvent {
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn init_reveal_irv_winner_comp_def(ctx: Context<InitRevealIrvWinnerCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Runs the instant-runoff count of a ranked-choice poll and reveals the winner.
+    ///
+    /// Only the poll authority can call this, once voting has ended. The elimination rounds
+    /// run inside MPC. For polls created with `Disclosure::FullTally` each round's counts
+    /// are revealed too and kept in the ranked tally account. The callback finalizes the poll.
+    ///
+    /// # Arguments
+    /// * `id` - The poll ID to reveal the winner for
+    pub fn reveal_irv_winner(
+        ctx: Context<RevealIrvWinner>,
+        computation_offset: u64,
+        id: u32,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.payer.key() == ctx.accounts.poll_acc.authority,
+            ErrorCode::InvalidAuthority
+        );
+        require!(
+            ctx.accounts.poll_acc.method == VotingMethod::RankedChoice,
+            ErrorCode::WrongBallotType
+        );
+        ctx.accounts.poll_acc.require_revealable()?;
+
+        msg!("Revealing instant-runoff winner for poll with id {}", id);
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(ctx.accounts.ranked_tally.nonce)
+            .account(
+                ctx.accounts.ranked_tally.key(),
+                // Offset calculation: 8 bytes (discriminator) + 1 byte (bump)
+                8 + 1,
+                32 * RANKED_CHUNKS as u32, // Every packed counter chunk, 32 bytes each
+            )
+            .plaintext_u8(ctx.accounts.poll_acc.options.len() as u8)
+            .plaintext_bool(ctx.accounts.poll_acc.disclosure == Disclosure::FullTally)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RevealIrvWinnerCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.poll_acc.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.ranked_tally.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "reveal_irv_winner")]
+    pub fn reveal_irv_winner_callback(
+        ctx: Context<RevealIrvWinnerCallback>,
+        output: SignedComputationOutputs<RevealIrvWinnerOutput>,
+    ) -> Result<()> {
+        let (winning_option, tie, rounds) = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealIrvWinnerOutput {
+                field_0:
+                    RevealIrvWinnerOutputStruct0 {
+                        field_0: winning_option,
+                        field_1: tie,
+                        field_2: rounds,
+                    },
+            }) => (winning_option, tie, rounds),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let poll = &mut ctx.accounts.poll_acc;
+        if poll.status == PollStatus::Finalized {
+            return Ok(());
+        }
+        poll.finalize(PollResult {
+            winning_option,
+            tie,
+        });
+        if poll.disclosure == Disclosure::FullTally {
+            ctx.accounts.ranked_tally.rounds = Some(rounds);
+        }
+
+        emit!(RevealIrvWinnerEvent {
+            id: poll.id,
+            winning_option,
+            tie,
+            rounds,
+        });
+
+        Ok(())
+    }
+}
+
+#[queue_computation_accounts("init_vote_stats", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, id: u32)]
+pub struct CreateNewPoll<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_VOTE_STATS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        mut,
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PollAccount::INIT_SPACE,
+        seeds = [b"poll", payer.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub poll_acc: Account<'info, PollAccount>,
+}
+
+#[callback_accounts("init_vote_stats")]
+#[derive(Accounts)]
+pub struct InitVoteStatsCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_VOTE_STATS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    /// CHECK: poll_acc, checked by the callback account key passed in queue_computation
+    #[account(mut)]
+    pub poll_acc: Account<'info, PollAccount>,
+}
+
+#[init_computation_definition_accounts("init_vote_stats", payer)]
+#[derive(Accounts)]
+pub struct InitVoteStatsCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table, checked by arcium program.
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program is the Address Lookup Table program.
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("vote", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, _id: u32)]
+pub struct Vote<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_VOTE)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        mut,
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    /// CHECK: Poll authority pubkey
+    #[account(
+        address = poll_acc.authority,
+    )]
+    pub authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll", authority.key().as_ref(), _id.to_le_bytes().as_ref()],
+        bump = poll_acc.bump,
+        has_one = authority
+    )]
+    pub poll_acc: Box<Account<'info, PollAccount>>,
+    /// Created on the voter's first ballot. A record that already exists is rejected in `vote`.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + VoterRecord::INIT_SPACE,
+        seeds = [b"voter", poll_acc.key().as_ref(), payer.key().as_ref()],
+        bump,
+    )]
+    pub voter_record: Box<Account<'info, VoterRecord>>,
+    /// Tokens the voter locked in the poll, for token-weighted polls only
+    #[account(
+        seeds = [b"escrow", poll_acc.key().as_ref(), payer.key().as_ref()],
+        bump,
+    )]
+    pub voter_escrow: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+}
+
+impl<'info> Vote<'info> {
+    /// Checks the delegations passed to `vote` and returns the weight they add, along with
+    /// the delegators' `VoterRecord` addresses.
+    ///
+    /// `delegations` holds, for each delegator, their `Delegation` and their `VoterRecord` PDA,
+    /// followed by their token escrow in a token-weighted poll. The `VoterRecord` must not
+    /// exist yet, meaning the delegator hasn't voted or been counted. It is created here and
+    /// marked as delegated to the payer.
+    fn count_delegations(
+        &self,
+        delegations: &'info [AccountInfo<'info>],
+    ) -> Result<(u64, Vec<Pubkey>)> {
+        if delegations.is_empty() {
+            return Ok((0, Vec::new()));
+        }
+        let poll = &self.poll_acc;
+        // A delegate can't prove that their delegators are in the electorate
+        require!(
+            poll.eligibility_root.is_none(),
+            ErrorCode::DelegationNotAllowed
+        );
+        let group_len = if poll.weight_mint.is_some() { 3 } else { 2 };
+        require!(
+            delegations.len() % group_len == 0,
+            ErrorCode::InvalidDelegation
+        );
+
+        let poll_key = poll.key();
+        let space = 8 + VoterRecord::INIT_SPACE;
+        let lamports = Rent::get()?.minimum_balance(space);
+        let mut delegated_weight = 0;
+        let mut delegator_records = Vec::with_capacity(delegations.len() / group_len);
+        for group in delegations.chunks(group_len) {
+            let delegation = Account::<Delegation>::try_from(&group[0])?;
+            require_keys_eq!(
+                delegation.delegate,
+                self.payer.key(),
+                ErrorCode::InvalidDelegation
+            );
+            require!(
+                delegation.scope == poll_key || delegation.scope == poll.authority,
+                ErrorCode::InvalidDelegation
+            );
+
+            let escrow = match poll.weight_mint {
+                Some(_) => {
+                    let (escrow_key, _) = Pubkey::find_program_address(
+                        &[b"escrow", poll_key.as_ref(), delegation.delegator.as_ref()],
+                        &crate::ID,
+                    );
+                    require_keys_eq!(group[2].key(), escrow_key, ErrorCode::InvalidDelegation);
+                    Some(InterfaceAccount::<TokenAccount>::try_from(&group[2])?)
+                }
+                None => None,
+            };
+            let weight = poll.ballot_weight(escrow.as_ref())?;
+
+            let record_info = &group[1];
+            let (record_key, bump) = Pubkey::find_program_address(
+                &[b"voter", poll_key.as_ref(), delegation.delegator.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(record_info.key(), record_key, ErrorCode::InvalidDelegation);
+            require!(
+                record_info.data_is_empty(),
+                ErrorCode::DelegatorAlreadyVoted
+            );
+
+            anchor_lang::system_program::create_account(
+                CpiContext::new_with_signer(
+                    self.system_program.to_account_info(),
+                    anchor_lang::system_program::CreateAccount {
+                        from: self.payer.to_account_info(),
+                        to: record_info.clone(),
+                    },
+                    &[&[
+                        b"voter",
+                        poll_key.as_ref(),
+                        delegation.delegator.as_ref(),
+                        &[bump],
+                    ]],
+                ),
+                lamports,
+                space as u64,
+                &crate::ID,
+            )?;
+            let record = VoterRecord {
+                bump,
+                ballot: [0; 32],
+                ballot_nonce: 0,
+                weight,
+                pending: false,
+                delegated_to: Some(self.payer.key()),
+                delegated_weight: 0,
+                previous_weight: 0,
+            };
+            let mut data = record_info.try_borrow_mut_data()?;
+            record.try_serialize(&mut &mut data[..])?;
+
+            delegated_weight =
+                u64::checked_add(delegated_weight, weight).ok_or(ErrorCode::WeightLimitReached)?;
+            delegator_records.push(record_key);
+        }
+        Ok((delegated_weight, delegator_records))
+    }
+}
+
+#[callback_accounts("vote")]
+#[derive(Accounts)]
+pub struct VoteCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_VOTE)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub poll_acc: Account<'info, PollAccount>,
+    #[account(mut)]
+    pub voter_record: Account<'info, VoterRecord>,
+    /// The voter, refunded the rent of the records closed if the computation aborts
+    #[account(mut)]
+    pub payer: SystemAccount<'info>,
+}
+
+impl<'info> VoteCallback<'info> {
+    /// Undoes a vote whose computation aborted, so the voter and their delegators can vote
+    /// again.
+    ///
+    /// Nothing reached the tally, so the ballot's weight comes back out of the poll's total.
+    /// The voter's record and the delegators' records in `delegator_records` are closed,
+    /// refunding their rent to the payer who created them.
+    fn discard_vote(&mut self, delegator_records: &[AccountInfo<'info>]) -> Result<()> {
+        let payer = self.payer.to_account_info();
+        for record_info in delegator_records {
+            require_keys_eq!(*record_info.owner, crate::ID, ErrorCode::InvalidDelegation);
+            let record = VoterRecord::try_deserialize(&mut &record_info.try_borrow_data()?[..])?;
+            require!(
+                record.delegated_to == Some(payer.key()),
+                ErrorCode::InvalidDelegation
+            );
+            let lamports = record_info.lamports();
+            record_info.sub_lamports(lamports)?;
+            payer.add_lamports(lamports)?;
+            record_info.assign(&anchor_lang::system_program::ID);
+            record_info.resize(0)?;
+        }
+        self.poll_acc
+            .discard_ballot(&self.voter_record, &self.payer)
+    }
+}
+
+#[init_computation_definition_accounts("vote", payer)]
+#[derive(Accounts)]
+pub struct InitVoteCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table, checked by arcium program.
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program is the Address Lookup Table program.
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("change_vote", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, _id: u32)]
+pub struct ChangeVote<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHANGE_VOTE)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        mut,
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    /// CHECK: Poll authority pubkey
+    #[account(
+        address = poll_acc.authority,
+    )]
+    pub authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll", authority.key().as_ref(), _id.to_le_bytes().as_ref()],
+        bump = poll_acc.bump,
+        has_one = authority
+    )]
+    pub poll_acc: Box<Account<'info, PollAccount>>,
+    #[account(
+        mut,
+        seeds = [b"voter", poll_acc.key().as_ref(), payer.key().as_ref()],
+        bump = voter_record.bump,
+    )]
+    pub voter_record: Box<Account<'info, VoterRecord>>,
+    /// Tokens the voter locked in the poll, for token-weighted polls only
+    #[account(
+        seeds = [b"escrow", poll_acc.key().as_ref(), payer.key().as_ref()],
+        bump,
+    )]
+    pub voter_escrow: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    /// The delegate's record, when the voter is overriding a vote cast on their behalf
+    #[account(
+        mut,
+        seeds = [
+            b"voter",
+            poll_acc.key().as_ref(),
+            voter_record.delegated_to.unwrap_or_default().as_ref(),
+        ],
+        bump = delegate_record.bump,
+    )]
+    pub delegate_record: Option<Box<Account<'info, VoterRecord>>>,
+}
+
+#[callback_accounts("change_vote")]
+#[derive(Accounts)]
+pub struct ChangeVoteCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHANGE_VOTE)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub poll_acc: Account<'info, PollAccount>,
+    #[account(mut)]
+    pub voter_record: Account<'info, VoterRecord>,
+    /// The delegate's record, when the change overrides a delegated vote
+    #[account(mut)]
+    pub delegate_record: Option<Account<'info, VoterRecord>>,
+}
+
+impl<'info> ChangeVoteCallback<'info> {
+    /// Undoes a change whose computation aborted. The tally still holds the recorded ballot,
+    /// so the weight it was counted with goes back in place of the new one.
+    fn restore_ballot(&mut self) {
+        let voter_record = &mut self.voter_record;
+        self.poll_acc.total_weight = self
+            .poll_acc
+            .total_weight
+            .saturating_sub(voter_record.weight)
+            .saturating_add(voter_record.previous_weight);
+        voter_record.weight = voter_record.previous_weight;
+        voter_record.pending = false;
+        if let Some(delegate_record) = self.delegate_record.as_mut() {
+            delegate_record.pending = false;
+        }
+    }
+}
+
+#[init_computation_definition_accounts("change_vote", payer)]
+#[derive(Accounts)]
+pub struct InitChangeVoteCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table, checked by arcium program.
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program is the Address Lookup Table program.
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("vote_quadratic", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, _id: u32)]
+pub struct VoteQuadratic<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_VOTE_QUADRATIC)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        mut,
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    /// CHECK: Poll authority pubkey
+    #[account(
+        address = poll_acc.authority,
+    )]
+    pub authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll", authority.key().as_ref(), _id.to_le_bytes().as_ref()],
+        bump = poll_acc.bump,
+        has_one = authority
+    )]
+    pub poll_acc: Box<Account<'info, PollAccount>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + VoterRecord::INIT_SPACE,
+        seeds = [b"voter", poll_acc.key().as_ref(), payer.key().as_ref()],
+        bump,
+    )]
+    pub voter_record: Box<Account<'info, VoterRecord>>,
+    /// Tokens the voter locked in the poll, for token-weighted polls only
+    #[account(
+        seeds = [b"escrow", poll_acc.key().as_ref(), payer.key().as_ref()],
+        bump,
+    )]
+    pub voter_escrow: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+}
+
+#[callback_accounts("vote_quadratic")]
+#[derive(Accounts)]
+pub struct VoteQuadraticCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_VOTE_QUADRATIC)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub poll_acc: Account<'info, PollAccount>,
+    #[account(mut)]
+    pub voter_record: Account<'info, VoterRecord>,
+    /// The voter, refunded the rent of their record if the computation aborts
+    #[account(mut)]
+    pub payer: SystemAccount<'info>,
+}
+
+#[init_computation_definition_accounts("vote_quadratic", payer)]
+#[derive(Accounts)]
+pub struct InitVoteQuadraticCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table, checked by arcium program.
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program is the Address Lookup Table program.
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("reveal_result", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, id: u32)]
+pub struct RevealVotingResult<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        mut,
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        seeds = [b"poll", payer.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump = poll_acc.bump
+    )]
+    pub poll_acc: Account<'info, PollAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(scope: Pubkey)]
+pub struct DelegateVote<'info> {
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+    #[account(
+        init,
+        payer = delegator,
+        space = 8 + Delegation::INIT_SPACE,
+        seeds = [b"delegation", scope.as_ref(), delegator.key().as_ref()],
+        bump,
+    )]
+    pub delegation: Account<'info, Delegation>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(scope: Pubkey)]
+pub struct RevokeDelegation<'info> {
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+    #[account(
+        mut,
+        close = delegator,
+        seeds = [b"delegation", scope.as_ref(), delegator.key().as_ref()],
+        bump = delegation.bump,
+        has_one = delegator,
+    )]
+    pub delegation: Account<'info, Delegation>,
+}
+
+#[derive(Accounts)]
+#[instruction(_id: u32)]
+pub struct LockTokens<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    /// CHECK: Poll authority pubkey
+    #[account(
+        address = poll_acc.authority,
+    )]
+    pub authority: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"poll", authority.key().as_ref(), _id.to_le_bytes().as_ref()],
+        bump = poll_acc.bump,
+        has_one = authority
+    )]
+    pub poll_acc: Box<Account<'info, PollAccount>>,
+    #[account(
+        address = poll_acc.weight_mint.unwrap_or_default() @ ErrorCode::NotTokenWeighted,
+        mint::token_program = token_program,
+    )]
+    pub weight_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint = weight_mint,
+        token::authority = voter,
+        token::token_program = token_program,
+    )]
+    pub voter_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = voter,
+        seeds = [b"escrow", poll_acc.key().as_ref(), voter.key().as_ref()],
+        bump,
+        token::mint = weight_mint,
+        token::authority = poll_acc,
+        token::token_program = token_program,
+    )]
+    pub voter_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(_id: u32)]
+pub struct WithdrawTokens<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    /// CHECK: Poll authority pubkey
+    #[account(
+        address = poll_acc.authority,
+    )]
+    pub authority: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"poll", authority.key().as_ref(), _id.to_le_bytes().as_ref()],
+        bump = poll_acc.bump,
+        has_one = authority
+    )]
+    pub poll_acc: Box<Account<'info, PollAccount>>,
+    #[account(
+        address = poll_acc.weight_mint.unwrap_or_default() @ ErrorCode::NotTokenWeighted,
+        mint::token_program = token_program,
+    )]
+    pub weight_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint = weight_mint,
+        token::authority = voter,
+        token::token_program = token_program,
+    )]
+    pub voter_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"escrow", poll_acc.key().as_ref(), voter.key().as_ref()],
+        bump,
+    )]
+    pub voter_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u32)]
+pub struct ClosePoll<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll", authority.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump = poll_acc.bump,
+        has_one = authority @ ErrorCode::InvalidAuthority,
+    )]
+    pub poll_acc: Account<'info, PollAccount>,
+}
+
+#[callback_accounts("reveal_result")]
+#[derive(Accounts)]
+pub struct RevealResultCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub poll_acc: Account<'info, PollAccount>,
+}
+
+#[init_computation_definition_accounts("reveal_result", payer)]
+#[derive(Accounts)]
+pub struct InitRevealResultCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table, checked by arcium program.
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program is the Address Lookup Table program.
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("reveal_tally", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, id: u32)]
+pub struct RevealTally<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_TALLY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        mut,
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        seeds = [b"poll", payer.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump = poll_acc.bump
+    )]
+    pub poll_acc: Account<'info, PollAccount>,
+}
 
-        emit!(RevealResultEvent { output: o });
+#[callback_accounts("reveal_tally")]
+#[derive(Accounts)]
+pub struct RevealTallyCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_TALLY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub poll_acc: Account<'info, PollAccount>,
+}
 
-        Ok(())
-    }
+#[init_computation_definition_accounts("reveal_tally", payer)]
+#[derive(Accounts)]
+pub struct InitRevealTallyCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table, checked by arcium program.
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program is the Address Lookup Table program.
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
 }
 
-#[queue_computation_accounts("init_vote_stats", payer)]
+#[queue_computation_accounts("init_ranked_stats", payer)]
 #[derive(Accounts)]
 #[instruction(computation_offset: u64, id: u32)]
-pub struct CreateNewPoll<'info> {
+pub struct InitRankedTally<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(
@@ -276,7 +2388,7 @@ pub struct CreateNewPoll<'info> {
     /// CHECK: computation_account, checked by the arcium program.
     pub computation_account: UncheckedAccount<'info>,
     #[account(
-        address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_VOTE_STATS)
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_RANKED_STATS)
     )]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(
@@ -296,22 +2408,27 @@ pub struct CreateNewPoll<'info> {
     pub clock_account: Account<'info, ClockAccount>,
     pub system_program: Program<'info, System>,
     pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        seeds = [b"poll", payer.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump = poll_acc.bump,
+    )]
+    pub poll_acc: Box<Account<'info, PollAccount>>,
     #[account(
         init,
         payer = payer,
-        space = 8 + PollAccount::INIT_SPACE,
-        seeds = [b"poll", payer.key().as_ref(), id.to_le_bytes().as_ref()],
+        space = 8 + RankedTally::INIT_SPACE,
+        seeds = [b"ranked_tally", poll_acc.key().as_ref()],
         bump,
     )]
-    pub poll_acc: Account<'info, PollAccount>,
+    pub ranked_tally: Box<Account<'info, RankedTally>>,
 }
 
-#[callback_accounts("init_vote_stats")]
+#[callback_accounts("init_ranked_stats")]
 #[derive(Accounts)]
-pub struct InitVoteStatsCallback<'info> {
+pub struct InitRankedStatsCallback<'info> {
     pub arcium_program: Program<'info, Arcium>,
     #[account(
-        address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_VOTE_STATS)
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_RANKED_STATS)
     )]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(
@@ -327,14 +2444,13 @@ pub struct InitVoteStatsCallback<'info> {
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar, checked by the account constraint
     pub instructions_sysvar: AccountInfo<'info>,
-    /// CHECK: poll_acc, checked by the callback account key passed in queue_computation
     #[account(mut)]
-    pub poll_acc: Account<'info, PollAccount>,
+    pub ranked_tally: Account<'info, RankedTally>,
 }
 
-#[init_computation_definition_accounts("init_vote_stats", payer)]
+#[init_computation_definition_accounts("init_ranked_stats", payer)]
 #[derive(Accounts)]
-pub struct InitVoteStatsCompDef<'info> {
+pub struct InitRankedStatsCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(
@@ -356,10 +2472,10 @@ pub struct InitVoteStatsCompDef<'info> {
     pub system_program: Program<'info, System>,
 }
 
-#[queue_computation_accounts("vote", payer)]
+#[queue_computation_accounts("vote_ranked", payer)]
 #[derive(Accounts)]
 #[instruction(computation_offset: u64, _id: u32)]
-pub struct Vote<'info> {
+pub struct VoteRanked<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(
@@ -394,7 +2510,7 @@ pub struct Vote<'info> {
     /// CHECK: computation_account, checked by the arcium program.
     pub computation_account: UncheckedAccount<'info>,
     #[account(
-        address = derive_comp_def_pda!(COMP_DEF_OFFSET_VOTE)
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_VOTE_RANKED)
     )]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(
@@ -420,6 +2536,7 @@ pub struct Vote<'info> {
     )]
     pub authority: UncheckedAccount<'info>,
     #[account(
+        mut,
         seeds = [b"poll", authority.key().as_ref(), _id.to_le_bytes().as_ref()],
         bump = poll_acc.bump,
         has_one = authority
@@ -433,14 +2550,25 @@ pub struct Vote<'info> {
         bump,
     )]
     pub voter_record: Box<Account<'info, VoterRecord>>,
+    /// Tokens the voter locked in the poll, for token-weighted polls only
+    #[account(
+        seeds = [b"escrow", poll_acc.key().as_ref(), payer.key().as_ref()],
+        bump,
+    )]
+    pub voter_escrow: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(
+        seeds = [b"ranked_tally", poll_acc.key().as_ref()],
+        bump = ranked_tally.bump,
+    )]
+    pub ranked_tally: Box<Account<'info, RankedTally>>,
 }
 
-#[callback_accounts("vote")]
+#[callback_accounts("vote_ranked")]
 #[derive(Accounts)]
-pub struct VoteCallback<'info> {
+pub struct VoteRankedCallback<'info> {
     pub arcium_program: Program<'info, Arcium>,
     #[account(
-        address = derive_comp_def_pda!(COMP_DEF_OFFSET_VOTE)
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_VOTE_RANKED)
     )]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(
@@ -457,12 +2585,19 @@ pub struct VoteCallback<'info> {
     /// CHECK: instructions_sysvar, checked by the account constraint
     pub instructions_sysvar: AccountInfo<'info>,
     #[account(mut)]
+    pub ranked_tally: Account<'info, RankedTally>,
+    #[account(mut)]
     pub poll_acc: Account<'info, PollAccount>,
+    #[account(mut)]
+    pub voter_record: Account<'info, VoterRecord>,
+    /// The voter, refunded the rent of their record if the computation aborts
+    #[account(mut)]
+    pub payer: SystemAccount<'info>,
 }
 
-#[init_computation_definition_accounts("vote", payer)]
+#[init_computation_definition_accounts("vote_ranked", payer)]
 #[derive(Accounts)]
-pub struct InitVoteCompDef<'info> {
+pub struct InitVoteRankedCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(
@@ -484,10 +2619,10 @@ pub struct InitVoteCompDef<'info> {
     pub system_program: Program<'info, System>,
 }
 
-#[queue_computation_accounts("reveal_result", payer)]
+#[queue_computation_accounts("reveal_irv_winner", payer)]
 #[derive(Accounts)]
 #[instruction(computation_offset: u64, id: u32)]
-pub struct RevealVotingResult<'info> {
+pub struct RevealIrvWinner<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(
@@ -522,7 +2657,7 @@ pub struct RevealVotingResult<'info> {
     /// CHECK: computation_account, checked by the arcium program.
     pub computation_account: UncheckedAccount<'info>,
     #[account(
-        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL)
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_IRV_WINNER)
     )]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(
@@ -546,15 +2681,20 @@ pub struct RevealVotingResult<'info> {
         seeds = [b"poll", payer.key().as_ref(), id.to_le_bytes().as_ref()],
         bump = poll_acc.bump
     )]
-    pub poll_acc: Account<'info, PollAccount>,
+    pub poll_acc: Box<Account<'info, PollAccount>>,
+    #[account(
+        seeds = [b"ranked_tally", poll_acc.key().as_ref()],
+        bump = ranked_tally.bump,
+    )]
+    pub ranked_tally: Box<Account<'info, RankedTally>>,
 }
 
-#[callback_accounts("reveal_result")]
+#[callback_accounts("reveal_irv_winner")]
 #[derive(Accounts)]
-pub struct RevealResultCallback<'info> {
+pub struct RevealIrvWinnerCallback<'info> {
     pub arcium_program: Program<'info, Arcium>,
     #[account(
-        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL)
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_IRV_WINNER)
     )]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(
@@ -570,11 +2710,15 @@ pub struct RevealResultCallback<'info> {
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar, checked by the account constraint
     pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub poll_acc: Account<'info, PollAccount>,
+    #[account(mut)]
+    pub ranked_tally: Account<'info, RankedTally>,
 }
 
-#[init_computation_definition_accounts("reveal_result", payer)]
+#[init_computation_definition_accounts("reveal_irv_winner", payer)]
 #[derive(Accounts)]
-pub struct InitRevealResultCompDef<'info> {
+pub struct InitRevealIrvWinnerCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(
@@ -602,8 +2746,8 @@ pub struct InitRevealResultCompDef<'info> {
 pub struct PollAccount {
     /// PDA bump seed
     pub bump: u8,
-    /// Encrypted vote counters: [yes_count, no_count] as 32-byte ciphertexts
-    pub vote_state: [[u8; 32]; 2],
+    /// Encrypted vote counters as 32-byte ciphertexts: one per option slot, then invalid ballots
+    pub vote_state: [[u8; 32]; TALLY_SLOTS],
     /// Unique identifier for this poll
     pub id: u32,
     /// Public key of the poll creator (only they can reveal results)
@@ -613,14 +2757,268 @@ pub struct PollAccount {
     /// The poll question (max 50 characters)
     #[max_len(50)]
     pub question: String,
+    /// The answers voters choose from, indexed by the encrypted vote
+    #[max_len(MAX_OPTIONS, MAX_OPTION_LEN)]
+    pub options: Vec<String>,
+    /// How much of the tally the authority may reveal
+    pub disclosure: Disclosure,
+    /// Exact counts, set once revealed with `reveal_tally`
+    pub tally: Option<RevealedTally>,
+    /// Unix timestamp from which votes are accepted
+    pub starts_at: i64,
+    /// Unix timestamp after which votes are no longer accepted
+    pub ends_at: i64,
+    /// Whether the poll is open, closed early, or finalized with its result revealed
+    pub status: PollStatus,
+    /// Final outcome, stored once when the poll is finalized
+    pub result: Option<PollResult>,
+    /// Token whose locked amount sets each vote's weight, `None` for one vote per voter
+    pub weight_mint: Option<Pubkey>,
+    /// Merkle root of the eligible voters, `None` if anyone can vote
+    pub eligibility_root: Option<[u8; 32]>,
+    /// How voters fill in their ballot
+    pub method: VotingMethod,
+    /// Whether voters may replace their vote with `change_vote` while the poll is open
+    pub allow_vote_changes: bool,
+    /// Combined weight of every ballot cast, capped so the encrypted counters can't overflow
+    pub total_weight: u64,
+}
+
+impl PollAccount {
+    /// Takes the weight of a ballot whose computation aborted back out of the total and closes
+    /// its record, refunding the rent to the voter who paid it.
+    fn discard_ballot<'info>(
+        &mut self,
+        voter_record: &Account<'info, VoterRecord>,
+        payer: &SystemAccount<'info>,
+    ) -> Result<()> {
+        self.total_weight = self.total_weight.saturating_sub(voter_record.weight);
+        voter_record.close(payer.to_account_info())
+    }
+
+    /// Whether voting is over, either because the window has ended or the poll was closed.
+    fn voting_ended(&self, now: i64) -> bool {
+        self.status != PollStatus::Open || now > self.ends_at
+    }
+
+    /// Checks that the result can be revealed: voting is over and nothing was revealed yet.
+    fn require_revealable(&self) -> Result<()> {
+        require!(
+            self.status != PollStatus::Finalized,
+            ErrorCode::PollFinalized
+        );
+        require!(
+            self.voting_ended(Clock::get()?.unix_timestamp),
+            ErrorCode::VotingStillOpen
+        );
+        Ok(())
+    }
+
+    /// Checks that the poll is taking votes: its window has opened and it hasn't ended.
+    fn require_voting_open(&self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= self.starts_at, ErrorCode::VotingNotStarted);
+        require!(!self.voting_ended(now), ErrorCode::VotingClosed);
+        Ok(())
+    }
+
+    /// Adds a ballot's weight to the poll's total, rejecting it once the encrypted counters
+    /// could overflow. A quadratic ballot adds up to 255 votes times its weight to a counter,
+    /// so quadratic polls take less total weight than the others.
+    fn add_weight(&mut self, weight: u64) -> Result<()> {
+        let max_total = match self.method {
+            VotingMethod::Quadratic { .. } => u64::MAX / u8::MAX as u64,
+            VotingMethod::SingleChoice | VotingMethod::RankedChoice => u64::MAX,
+        };
+        self.total_weight = self
+            .total_weight
+            .checked_add(weight)
+            .filter(|&total| total <= max_total)
+            .ok_or(ErrorCode::WeightLimitReached)?;
+        Ok(())
+    }
+
+    /// Weight of a ballot: the tokens the voter locked in `escrow` in a token-weighted poll,
+    /// 1 otherwise.
+    fn ballot_weight(&self, escrow: Option<&InterfaceAccount<TokenAccount>>) -> Result<u64> {
+        match self.weight_mint {
+            Some(_) => {
+                let escrow = escrow.ok_or(ErrorCode::MissingTokenAccount)?;
+                require!(escrow.amount > 0, ErrorCode::NoVotingWeight);
+                Ok(escrow.amount)
+            }
+            None => Ok(1),
+        }
+    }
+
+    /// Whether `voter` may vote, checking `proof` against the eligibility root if there is one.
+    ///
+    /// Leaves are `sha256(0x00 || voter)` and inner nodes `sha256(0x01 || a || b)`, with the two
+    /// children in ascending order. The proof lists the sibling hashes from the leaf up.
+    fn is_eligible(&self, voter: &Pubkey, proof: &[[u8; 32]]) -> bool {
+        let Some(root) = self.eligibility_root else {
+            return true;
+        };
+        if proof.len() > MAX_PROOF_DEPTH {
+            return false;
+        }
+
+        let mut node = hashv(&[&[MERKLE_LEAF_PREFIX], voter.as_ref()]).to_bytes();
+        for sibling in proof {
+            let (left, right) = if node <= *sibling {
+                (node, *sibling)
+            } else {
+                (*sibling, node)
+            };
+            node = hashv(&[&[MERKLE_NODE_PREFIX], &left, &right]).to_bytes();
+        }
+        node == root
+    }
+
+    fn finalize(&mut self, result: PollResult) {
+        self.status = PollStatus::Finalized;
+        self.result = Some(result);
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum PollStatus {
+    /// Accepting votes while the voting window lasts
+    Open,
+    /// Closed early by the authority
+    Closed,
+    /// The result has been revealed and can't change
+    Finalized,
+}
+
+/// Outcome of a finalized poll.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct PollResult {
+    /// Option with the most votes, the lowest such index on a tie
+    pub winning_option: u8,
+    /// Whether another option has as many votes as the winning one
+    pub tie: bool,
+}
+
+/// How much of a poll's tally can be revealed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum Disclosure {
+    /// Only the winning option and whether it was tied can be revealed
+    WinnerOnly,
+    /// The authority may also reveal the exact count of every option
+    FullTally,
+}
+
+/// How voters fill in their ballot.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum VotingMethod {
+    /// Each ballot names one option, cast with `vote`
+    SingleChoice,
+    /// Each ballot splits votes across options, cast with `vote_quadratic`. Giving an option
+    /// `n` votes costs `n * n` of the voter's `credits`.
+    Quadratic { credits: u64 },
+    /// Each ballot ranks up to 4 options, cast with `vote_ranked` and counted by instant runoff
+    RankedChoice,
+}
+
+/// Decrypted vote counts of a poll. In a token-weighted poll each count is a sum of locked tokens.
+/// In a quadratic poll the counts are votes rather than ballots, so turnout adds up votes too.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct RevealedTally {
+    /// Votes for each option slot, zero past the poll's option count
+    pub counts: [u64; MAX_OPTIONS],
+    /// Ballots naming an option the poll doesn't have
+    pub invalid_votes: u64,
+    /// Every ballot counted, valid or not. Wider than the counts, which it adds up.
+    pub turnout: u128,
+    /// Option with the most votes, the lowest such index on a tie
+    pub winning_option: u8,
+    /// Whether another option has as many votes as the winning one
+    pub tie: bool,
+}
+
+impl RevealedTally {
+    fn new(counts: [u64; MAX_OPTIONS], invalid_votes: u64, num_options: usize) -> Self {
+        let options = &counts[..num_options];
+        let most_votes = options.iter().copied().max().unwrap_or(0);
+        let winning_option = options
+            .iter()
+            .position(|&count| count == most_votes)
+            .unwrap_or(0) as u8;
+        let tie = options.iter().filter(|&&count| count == most_votes).count() > 1;
+
+        Self {
+            counts,
+            invalid_votes,
+            turnout: counts.iter().map(|&count| count as u128).sum::<u128>()
+                + invalid_votes as u128,
+            winning_option,
+            tie,
+        }
+    }
+}
+
+/// Encrypted instant-runoff counters of a ranked-choice poll.
+#[account]
+#[derive(InitSpace)]
+pub struct RankedTally {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Encrypted counters (Pack<[u64; 28]>) as 32-byte chunks
+    pub state: [[u8; 32]; RANKED_CHUNKS],
+    /// Cryptographic nonce for the encrypted counters
+    pub nonce: u128,
+    /// Each round's count per option, set once revealed for a fully disclosed poll
+    pub rounds: Option<[[u64; MAX_RANKED_OPTIONS]; IRV_ROUNDS]>,
+}
+
+/// A voter's assignment of their vote to a delegate.
+#[account]
+#[derive(InitSpace)]
+pub struct Delegation {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Voter handing over their vote
+    pub delegator: Pubkey,
+    /// Who votes on the delegator's behalf
+    pub delegate: Pubkey,
+    /// Poll account the delegation applies to, or poll authority for all of their polls
+    pub scope: Pubkey,
 }
 
 /// Per-poll voter deduplication record.
+///
+/// For single-choice polls it also keeps the voter's current vote, encrypted for the MXE,
+/// so `change_vote` can take it back out of the tally. A delegator's record is created by
+/// their delegate's vote and holds no ballot, only the weight cast for them.
 #[account]
 #[derive(InitSpace)]
 pub struct VoterRecord {
     /// PDA bump seed
     pub bump: u8,
+    /// The voter's current vote as a 32-byte ciphertext
+    pub ballot: [u8; 32],
+    /// Cryptographic nonce for the encrypted vote
+    pub ballot_nonce: u128,
+    /// Weight the current vote was counted with
+    pub weight: u64,
+    /// Whether a vote or change is queued and its ballot not recorded yet, or a delegator's
+    /// override of it is queued
+    pub pending: bool,
+    /// The delegate who cast this voter's vote, until the voter overrides it with `change_vote`
+    pub delegated_to: Option<Pubkey>,
+    /// Part of `weight` cast on behalf of delegators
+    pub delegated_weight: u64,
+    /// Weight of the recorded ballot while a change is pending, restored if the change aborts
+    pub previous_weight: u64,
+}
+
+impl VoterRecord {
+    fn record_ballot(&mut self, ballot: [u8; 32], nonce: u128) {
+        self.ballot = ballot;
+        self.ballot_nonce = nonce;
+        self.pending = false;
+    }
 }
 
 #[error_code]
@@ -631,6 +3029,58 @@ pub enum ErrorCode {
     AbortedComputation,
     #[msg("Cluster not set")]
     ClusterNotSet,
+    #[msg("Polls must have between 2 and 8 options")]
+    InvalidOptionCount,
+    #[msg("Option labels can be at most 32 bytes")]
+    OptionTooLong,
+    #[msg("This poll only discloses its winning option")]
+    TallyNotDisclosable,
+    #[msg("The voting window must end after it starts, and in the future")]
+    InvalidVotingWindow,
+    #[msg("Voting has not started yet")]
+    VotingNotStarted,
+    #[msg("Voting is closed")]
+    VotingClosed,
+    #[msg("Voting is still open")]
+    VotingStillOpen,
+    #[msg("The poll result is already final")]
+    PollFinalized,
+    #[msg("Token-weighted polls need the voter's token escrow")]
+    MissingTokenAccount,
+    #[msg("The voter has no tokens locked in the poll")]
+    NoVotingWeight,
+    #[msg("The voter is not in the poll's electorate")]
+    NotEligible,
+    #[msg("Quadratic polls need a credit budget above zero")]
+    InvalidCreditBudget,
+    #[msg("This ballot doesn't match the poll's voting method")]
+    WrongBallotType,
+    #[msg("Ranked-choice polls can have at most 4 options")]
+    TooManyRankedOptions,
+    #[msg("Ranked-choice polls are revealed with reveal_irv_winner")]
+    RankedChoiceReveal,
+    #[msg("Only single-choice polls can allow vote changes")]
+    VoteChangesUnsupported,
+    #[msg("This poll doesn't allow changing votes")]
+    VoteChangesNotAllowed,
+    #[msg("The voter's previous ballot hasn't been counted yet")]
+    BallotPending,
+    #[msg("Invalid delegation")]
+    InvalidDelegation,
+    #[msg("Polls restricted to an electorate don't accept delegated votes")]
+    DelegationNotAllowed,
+    #[msg("The delegator has already voted or been counted")]
+    DelegatorAlreadyVoted,
+    #[msg("Overriding a delegated vote needs the delegate's record")]
+    MissingDelegateRecord,
+    #[msg("This poll isn't token-weighted")]
+    NotTokenWeighted,
+    #[msg("The poll can't take any more voting weight")]
+    WeightLimitReached,
+    #[msg("The voter has already voted in this poll")]
+    AlreadyVoted,
+    #[msg("Your vote was cast by a delegate; use change_vote to override it")]
+    VoteDelegated,
 }
 
 #[event]
@@ -638,7 +3088,56 @@ pub struct VoteEvent {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct VoteAbortedEvent {
+    pub voter: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DelegationEvent {
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+    pub scope: Pubkey,
+}
+
+#[event]
+pub struct VoteChangedEvent {
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VoteChangeAbortedEvent {
+    pub voter_record: Pubkey,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct RevealResultEvent {
-    pub output: bool,
+    /// Index of the option with the most votes, the lowest index on a tie
+    pub winning_option: u8,
+    /// Whether another option has as many votes as the winning one
+    pub tie: bool,
+}
+
+#[event]
+pub struct RevealTallyEvent {
+    pub id: u32,
+    /// Votes for each option slot, zero past the poll's option count
+    pub counts: [u64; MAX_OPTIONS],
+    pub invalid_votes: u64,
+    pub turnout: u128,
+    pub winning_option: u8,
+    pub tie: bool,
+}
+
+#[event]
+pub struct RevealIrvWinnerEvent {
+    pub id: u32,
+    /// Winner of the instant-runoff count, the lowest index if the final two are tied
+    pub winning_option: u8,
+    /// Whether the final two options are tied
+    pub tie: bool,
+    /// Each round's count per option, all zero unless the poll is fully disclosed
+    pub rounds: [[u64; MAX_RANKED_OPTIONS]; IRV_ROUNDS],
 }
@@ -1,6 +1,12 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { PublicKey } from "@solana/web3.js";
+import {
+  createAssociatedTokenAccount,
+  createMint,
+  mintTo,
+  TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
 import { Voting } from "../target/types/voting";
 import { randomBytes, createHash } from "crypto";
 import nacl from "tweetnacl";
@@ -49,6 +55,55 @@ function deriveEncryptionKey(
   return { privateKey, publicKey };
 }
 
+/**
+ * Builds the eligibility Merkle tree the voting program checks proofs against.
+ * Leaves are sha256(0x00 || voter) and inner nodes sha256(0x01 || a || b) with
+ * the two children sorted, so proofs need no left/right flags. An unpaired node
+ * moves up a level unchanged.
+ */
+function buildEligibilityTree(voters: PublicKey[]): {
+  root: Buffer;
+  proof: (voter: PublicKey) => Buffer[];
+} {
+  const sha256 = (...parts: Buffer[]) =>
+    createHash("sha256").update(Buffer.concat(parts)).digest();
+
+  const levels: Buffer[][] = [
+    voters.map((voter) => sha256(Buffer.from([0]), voter.toBuffer())),
+  ];
+  while (levels[levels.length - 1].length > 1) {
+    const level = levels[levels.length - 1];
+    const next: Buffer[] = [];
+    for (let i = 0; i < level.length; i += 2) {
+      if (i + 1 === level.length) {
+        next.push(level[i]);
+        continue;
+      }
+      const [left, right] =
+        Buffer.compare(level[i], level[i + 1]) <= 0
+          ? [level[i], level[i + 1]]
+          : [level[i + 1], level[i]];
+      next.push(sha256(Buffer.from([1]), left, right));
+    }
+    levels.push(next);
+  }
+
+  const proof = (voter: PublicKey) => {
+    let index = voters.findIndex((candidate) => candidate.equals(voter));
+    const siblings: Buffer[] = [];
+    for (const level of levels.slice(0, -1)) {
+      const sibling = index ^ 1;
+      if (sibling < level.length) {
+        siblings.push(level[sibling]);
+      }
+      index = Math.floor(index / 2);
+    }
+    return siblings;
+  };
+
+  return { root: levels[levels.length - 1][0], proof };
+}
+
 describe("Voting", () => {
   // Configure the client to use the local cluster.
   anchor.setProvider(anchor.AnchorProvider.env());
@@ -81,6 +136,7 @@ describe("Voting", () => {
 
   it("can vote on polls!", async () => {
     const POLL_IDS = [420, 421, 422];
+    const POLL_OPTIONS = ["Under 100", "100 to 500", "Over 500"];
     const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
 
     const mxePublicKey = await getMXEPublicKeyWithRetry(
@@ -126,7 +182,14 @@ describe("Voting", () => {
         .createNewPoll(
           pollComputationOffset,
           POLL_ID,
-          `Poll ${POLL_ID}: $SOL to 500?`
+          `Poll ${POLL_ID}: $SOL by year end?`,
+          POLL_OPTIONS,
+          { winnerOnly: {} },
+          ...votingWindow(),
+          null,
+          null,
+          { singleChoice: {} },
+          false
         )
         .accountsPartial({
           computationAccount: getComputationAccAddress(
@@ -161,7 +224,7 @@ describe("Voting", () => {
     }
 
     // Cast votes for each poll with different outcomes
-    const voteOutcomes = [true, false, true]; // Different outcomes for each poll
+    const voteOutcomes = [2, 0, 1]; // Option index chosen in each poll
     let firstPollPDA: PublicKey;
     let firstVoterRecordPDA: PublicKey;
     for (let i = 0; i < POLL_IDS.length; i++) {
@@ -202,7 +265,8 @@ describe("Voting", () => {
           POLL_ID,
           Array.from(ciphertext[0]),
           Array.from(publicKey),
-          new anchor.BN(deserializeLE(nonce).toString())
+          new anchor.BN(deserializeLE(nonce).toString()),
+          []
         )
         .accountsPartial({
           computationAccount: getComputationAccAddress(
@@ -222,6 +286,7 @@ describe("Voting", () => {
           authority: owner.publicKey,
           pollAcc: pollPDA,
           voterRecord: voterRecordPDA,
+          voterEscrow: null,
         })
         .rpc({
           skipPreflight: true,
@@ -249,10 +314,7 @@ describe("Voting", () => {
     console.log("\n--- Testing double-vote prevention ---");
     const DOUBLE_VOTE_POLL_ID = POLL_IDS[0];
     const doubleVoteNonce = randomBytes(16);
-    const doubleVoteCiphertext = cipher.encrypt(
-      [BigInt(true)],
-      doubleVoteNonce
-    );
+    const doubleVoteCiphertext = cipher.encrypt([BigInt(0)], doubleVoteNonce);
 
     const doubleVoteComputationOffset = new anchor.BN(randomBytes(8), "hex");
 
@@ -263,7 +325,8 @@ describe("Voting", () => {
           DOUBLE_VOTE_POLL_ID,
           Array.from(doubleVoteCiphertext[0]),
           Array.from(publicKey),
-          new anchor.BN(deserializeLE(doubleVoteNonce).toString())
+          new anchor.BN(deserializeLE(doubleVoteNonce).toString()),
+          []
         )
         .accountsPartial({
           computationAccount: getComputationAccAddress(
@@ -283,6 +346,7 @@ describe("Voting", () => {
           authority: owner.publicKey,
           pollAcc: firstPollPDA,
           voterRecord: firstVoterRecordPDA,
+          voterEscrow: null,
         })
         .rpc({
           preflightCommitment: "confirmed",
@@ -292,16 +356,24 @@ describe("Voting", () => {
       expect.fail("Double vote should have been rejected");
     } catch (error) {
       console.log("Double vote correctly rejected:", error.message);
-      expect(error.message).to.satisfy(
-        (msg: string) => msg.includes("already in use") || msg.includes("0x0")
-      );
+      expect(error.message).to.include("AlreadyVoted");
     }
 
-    // Reveal results for each poll
+    // Results stay sealed while the poll is open
+    try {
+      await queueRevealResult(POLL_IDS[0]);
+      expect.fail("The result should not be revealed while voting is open");
+    } catch (error) {
+      expect(error.message).to.include("VotingStillOpen");
+    }
+
+    // Close each poll and reveal its result
     for (let i = 0; i < POLL_IDS.length; i++) {
       const POLL_ID = POLL_IDS[i];
       const expectedOutcome = voteOutcomes[i];
 
+      await closePoll(POLL_ID);
+
       const revealEventPromise = awaitEvent("revealResultEvent");
 
       const revealComputationOffset = new anchor.BN(randomBytes(8), "hex");
@@ -344,163 +416,1511 @@ describe("Voting", () => {
       const revealEvent = await revealEventPromise;
       console.log(
         `Decrypted winner for poll ${POLL_ID} is `,
-        revealEvent.output
+        POLL_OPTIONS[revealEvent.winningOption]
+      );
+      expect(revealEvent.winningOption).to.equal(expectedOutcome);
+      expect(revealEvent.tie).to.equal(false);
+
+      const poll = await program.account.pollAccount.fetch(
+        pollPda(owner.publicKey, POLL_ID)
       );
-      expect(revealEvent.output).to.equal(expectedOutcome);
+      expect(poll.status).to.deep.equal({ finalized: {} });
+      expect(poll.result.winningOption).to.equal(expectedOutcome);
+    }
+
+    // A finalized result can't be recomputed
+    try {
+      await queueRevealResult(POLL_IDS[0]);
+      expect.fail("A finalized poll should not be revealed again");
+    } catch (error) {
+      expect(error.message).to.include("PollFinalized");
     }
   });
 
-  async function initVoteStatsCompDef(
-    program: Program<Voting>,
-    owner: anchor.web3.Keypair
-  ): Promise<string> {
-    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
-      "ComputationDefinitionAccount"
+  it("reveals the full tally of a fully disclosed poll", async () => {
+    const POLL_ID = 430;
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
     );
-    const offset = getCompDefAccOffset("init_vote_stats");
 
-    const compDefPDA = PublicKey.findProgramAddressSync(
-      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
-      getArciumProgramId()
-    )[0];
+    await initRevealTallyCompDef(program, owner);
 
-    console.log(
-      "Init vote stats computation definition pda is ",
-      compDefPDA.toBase58()
-    );
+    // Poll 420 was created with winner-only disclosure, so its counts stay hidden
+    const tallyComputationOffset = new anchor.BN(randomBytes(8), "hex");
+    try {
+      await program.methods
+        .revealTally(tallyComputationOffset, 420)
+        .accountsPartial({
+          computationAccount: getComputationAccAddress(
+            arciumEnv.arciumClusterOffset,
+            tallyComputationOffset
+          ),
+          clusterAccount: clusterAccount,
+          mxeAccount: getMXEAccAddress(program.programId),
+          mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+          executingPool: getExecutingPoolAccAddress(
+            arciumEnv.arciumClusterOffset
+          ),
+          compDefAccount: getCompDefAccAddress(
+            program.programId,
+            Buffer.from(getCompDefAccOffset("reveal_tally")).readUInt32LE()
+          ),
+        })
+        .rpc({ commitment: "confirmed" });
+      expect.fail("A winner-only poll should not reveal its tally");
+    } catch (error) {
+      expect(error.message).to.include("TallyNotDisclosable");
+    }
 
-    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
-    const mxeAccount = getMXEAccAddress(program.programId);
-    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
-    const lutAddress = getLookupTableAddress(
-      program.programId,
-      mxeAcc.lutOffsetSlot
+    await createPoll(POLL_ID, ["Alice", "Bob", "Carol"], { fullTally: {} });
+
+    // Two votes for Bob, one for Carol, and one ballot naming a missing option
+    const ballots = [1, 2, 1, 5];
+    for (const option of ballots) {
+      const voter = anchor.web3.Keypair.generate();
+      await airdrop(voter.publicKey);
+      await castVote(voter, mxePublicKey, owner.publicKey, POLL_ID, option);
+    }
+
+    await closePoll(POLL_ID);
+
+    // No votes are accepted once the poll is closed
+    const lateVoter = anchor.web3.Keypair.generate();
+    await airdrop(lateVoter.publicKey);
+    try {
+      await castVote(lateVoter, mxePublicKey, owner.publicKey, POLL_ID, 0);
+      expect.fail("A vote after the poll closed should have been rejected");
+    } catch (error) {
+      expect(error.message).to.include("VotingClosed");
+    }
+
+    const tallyEvent = await revealTally(POLL_ID);
+    expect(
+      tallyEvent.counts.slice(0, 3).map((c) => c.toNumber())
+    ).to.deep.equal([0, 2, 1]);
+    expect(tallyEvent.invalidVotes.toNumber()).to.equal(1);
+    expect(tallyEvent.turnout.toNumber()).to.equal(4);
+    expect(tallyEvent.winningOption).to.equal(1);
+    expect(tallyEvent.tie).to.equal(false);
+
+    const poll = await program.account.pollAccount.fetch(
+      pollPda(owner.publicKey, POLL_ID)
     );
+    expect(poll.tally.turnout.toNumber()).to.equal(4);
+  });
 
-    const sig = await program.methods
-      .initVoteStatsCompDef()
-      .accounts({
-        compDefAccount: compDefPDA,
-        payer: owner.publicKey,
-        mxeAccount,
-        addressLookupTable: lutAddress,
-      })
-      .signers([owner])
-      .rpc({
-        preflightCommitment: "confirmed",
-        commitment: "confirmed",
-      });
-    console.log("Init vote stats computation definition transaction", sig);
+  it("weights votes by locked tokens", async () => {
+    const POLL_ID = 440;
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
 
-    const rawCircuit = fs.readFileSync("build/init_vote_stats.arcis");
-    await uploadCircuit(
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
       provider as anchor.AnchorProvider,
-      "init_vote_stats",
-      program.programId,
-      rawCircuit,
-      true
+      program.programId
     );
 
-    return sig;
-  }
-
-  async function initVoteCompDef(
-    program: Program<Voting>,
-    owner: anchor.web3.Keypair
-  ): Promise<string> {
-    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
-      "ComputationDefinitionAccount"
+    const mint = await createMint(
+      provider.connection,
+      owner,
+      owner.publicKey,
+      null,
+      0
     );
-    const offset = getCompDefAccOffset("vote");
+    await createPoll(POLL_ID, ["Yes", "No"], { fullTally: {} }, mint);
 
-    const compDefPDA = PublicKey.findProgramAddressSync(
-      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
-      getArciumProgramId()
-    )[0];
+    // One large holder votes No, two smaller holders vote Yes
+    const holdings = [
+      { balance: 100, option: 1 },
+      { balance: 30, option: 0 },
+      { balance: 45, option: 0 },
+    ];
+    const poll = pollPda(owner.publicKey, POLL_ID);
+    const voters: [anchor.web3.Keypair, PublicKey][] = [];
+    for (const { balance, option } of holdings) {
+      const voter = anchor.web3.Keypair.generate();
+      await airdrop(voter.publicKey);
+      const tokenAccount = await createAssociatedTokenAccount(
+        provider.connection,
+        owner,
+        mint,
+        voter.publicKey
+      );
+      await mintTo(
+        provider.connection,
+        owner,
+        mint,
+        tokenAccount,
+        owner,
+        balance
+      );
+      await lockTokens(
+        voter,
+        owner.publicKey,
+        POLL_ID,
+        mint,
+        tokenAccount,
+        balance
+      );
+      await castVote(
+        voter,
+        mxePublicKey,
+        owner.publicKey,
+        POLL_ID,
+        option,
+        escrowPda(poll, voter.publicKey)
+      );
+      voters.push([voter, tokenAccount]);
+    }
 
-    console.log("Vote computation definition pda is ", compDefPDA.toBase58());
+    // A wallet holding none of the token has nothing to lock, so no vote
+    const emptyVoter = anchor.web3.Keypair.generate();
+    await airdrop(emptyVoter.publicKey);
+    const emptyAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      owner,
+      mint,
+      emptyVoter.publicKey
+    );
+    try {
+      await lockTokens(
+        emptyVoter,
+        owner.publicKey,
+        POLL_ID,
+        mint,
+        emptyAccount,
+        0
+      );
+      expect.fail("A voter without tokens should have been rejected");
+    } catch (error) {
+      expect(error.message).to.include("NoVotingWeight");
+    }
 
-    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
-    const mxeAccount = getMXEAccAddress(program.programId);
-    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
-    const lutAddress = getLookupTableAddress(
-      program.programId,
-      mxeAcc.lutOffsetSlot
+    // Locked tokens stay in escrow until voting ends, so they can't be reused
+    const [largest, largestAccount] = voters[0];
+    try {
+      await withdrawTokens(
+        largest,
+        owner.publicKey,
+        POLL_ID,
+        mint,
+        largestAccount
+      );
+      expect.fail("Tokens were withdrawn while the poll was open");
+    } catch (error) {
+      expect(error.message).to.include("VotingStillOpen");
+    }
+
+    await closePoll(POLL_ID);
+    await withdrawTokens(
+      largest,
+      owner.publicKey,
+      POLL_ID,
+      mint,
+      largestAccount
     );
+    const balance = await provider.connection.getTokenAccountBalance(
+      largestAccount
+    );
+    expect(balance.value.amount).to.equal("100");
 
-    const sig = await program.methods
-      .initVoteCompDef()
-      .accounts({
-        compDefAccount: compDefPDA,
-        payer: owner.publicKey,
-        mxeAccount,
-        addressLookupTable: lutAddress,
-      })
-      .signers([owner])
-      .rpc({
-        preflightCommitment: "confirmed",
-        commitment: "confirmed",
-      });
-    console.log("Init vote computation definition transaction", sig);
+    const tallyEvent = await revealTally(POLL_ID);
+    expect(
+      tallyEvent.counts.slice(0, 2).map((c) => c.toNumber())
+    ).to.deep.equal([75, 100]);
+    expect(tallyEvent.winningOption).to.equal(1);
+  });
 
-    const rawCircuit = fs.readFileSync("build/vote.arcis");
-    await uploadCircuit(
+  it("only counts voters on the eligibility list", async () => {
+    const POLL_ID = 450;
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
       provider as anchor.AnchorProvider,
-      "vote",
-      program.programId,
-      rawCircuit,
-      true
+      program.programId
     );
 
-    return sig;
-  }
+    const electorate = [0, 1, 2, 3, 4].map(() =>
+      anchor.web3.Keypair.generate()
+    );
+    const tree = buildEligibilityTree(electorate.map((v) => v.publicKey));
+    await createPoll(
+      POLL_ID,
+      ["Yes", "No"],
+      { fullTally: {} },
+      null,
+      tree.root
+    );
 
-  async function initRevealResultCompDef(
-    program: Program<Voting>,
-    owner: anchor.web3.Keypair
-  ): Promise<string> {
-    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
-      "ComputationDefinitionAccount"
+    for (const voter of electorate.slice(0, 3)) {
+      await airdrop(voter.publicKey);
+      await castVote(
+        voter,
+        mxePublicKey,
+        owner.publicKey,
+        POLL_ID,
+        0,
+        null,
+        tree.proof(voter.publicKey)
+      );
+    }
+
+    // An outsider is rejected, even when borrowing an eligible voter's proof
+    const outsider = anchor.web3.Keypair.generate();
+    await airdrop(outsider.publicKey);
+    try {
+      await castVote(
+        outsider,
+        mxePublicKey,
+        owner.publicKey,
+        POLL_ID,
+        1,
+        null,
+        tree.proof(electorate[3].publicKey)
+      );
+      expect.fail("A voter outside the electorate should have been rejected");
+    } catch (error) {
+      expect(error.message).to.include("NotEligible");
+    }
+
+    await closePoll(POLL_ID);
+    const tallyEvent = await revealTally(POLL_ID);
+    expect(
+      tallyEvent.counts.slice(0, 2).map((c) => c.toNumber())
+    ).to.deep.equal([3, 0]);
+  });
+
+  it("tallies quadratic ballots within the credit budget", async () => {
+    const POLL_ID = 460;
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
     );
-    const offset = getCompDefAccOffset("reveal_result");
 
-    const compDefPDA = PublicKey.findProgramAddressSync(
-      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
-      getArciumProgramId()
-    )[0];
+    await initVoteQuadraticCompDef(program, owner);
+    await createPoll(
+      POLL_ID,
+      ["Parks", "Roads", "Schools"],
+      { fullTally: {} },
+      null,
+      null,
+      { quadratic: { credits: new anchor.BN(9) } }
+    );
 
-    console.log(
-      "Reveal result computation definition pda is ",
-      compDefPDA.toBase58()
+    // Votes per option slot; the last two ballots are invalid
+    const ballots = [
+      [3, 0, 0, 0, 0, 0, 0, 0], // costs 9
+      [0, 2, 2, 0, 0, 0, 0, 0], // costs 8
+      [1, 2, 2, 0, 0, 0, 0, 0], // costs 9
+      [2, 2, 1, 0, 0, 0, 0, 0], // costs 9
+      [3, 1, 0, 0, 0, 0, 0, 0], // costs 10, over budget
+      [0, 0, 0, 1, 0, 0, 0, 0], // votes for an option the poll doesn't have
+    ];
+    for (const votes of ballots) {
+      const voter = anchor.web3.Keypair.generate();
+      await airdrop(voter.publicKey);
+      await castQuadraticVote(
+        voter,
+        mxePublicKey,
+        owner.publicKey,
+        POLL_ID,
+        votes
+      );
+    }
+
+    await closePoll(POLL_ID);
+    const tallyEvent = await revealTally(POLL_ID);
+    expect(
+      tallyEvent.counts.slice(0, 3).map((c) => c.toNumber())
+    ).to.deep.equal([6, 6, 5]);
+    expect(tallyEvent.invalidVotes.toNumber()).to.equal(2);
+    expect(tallyEvent.winningOption).to.equal(0);
+    expect(tallyEvent.tie).to.equal(true);
+  });
+
+  it("elects the instant-runoff winner of a ranked-choice poll", async () => {
+    const POLL_ID = 470;
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
     );
 
-    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
-    const mxeAccount = getMXEAccAddress(program.programId);
-    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
-    const lutAddress = getLookupTableAddress(
-      program.programId,
-      mxeAcc.lutOffsetSlot
+    await initRankedStatsCompDef(program, owner);
+    await initVoteRankedCompDef(program, owner);
+    await initRevealIrvWinnerCompDef(program, owner);
+    await createPoll(
+      POLL_ID,
+      ["Alice", "Bob", "Carol", "Dave"],
+      { fullTally: {} },
+      null,
+      null,
+      { rankedChoice: {} }
     );
+    await initRankedTally(POLL_ID);
 
-    const sig = await program.methods
-      .initRevealResultCompDef()
-      .accounts({
-        compDefAccount: compDefPDA,
-        payer: owner.publicKey,
-        mxeAccount,
-        addressLookupTable: lutAddress,
-      })
-      .signers([owner])
-      .rpc({
-        preflightCommitment: "confirmed",
-        commitment: "confirmed",
-      });
-    console.log("Init reveal result computation definition transaction", sig);
+    // Rankings from most to least preferred; 4 marks an unused position
+    const ballots = [
+      [0, 4, 4, 4],
+      [0, 4, 4, 4],
+      [1, 2, 4, 4],
+      [2, 1, 4, 4],
+      [3, 1, 4, 4],
+    ];
+    for (const ranking of ballots) {
+      const voter = anchor.web3.Keypair.generate();
+      await airdrop(voter.publicKey);
+      await castRankedVote(
+        voter,
+        mxePublicKey,
+        owner.publicKey,
+        POLL_ID,
+        ranking
+      );
+    }
 
-    const rawCircuit = fs.readFileSync("build/reveal_result.arcis");
-    await uploadCircuit(
+    await closePoll(POLL_ID);
+    const winnerEvent = await revealIrvWinner(POLL_ID);
+
+    // Alice leads on first preferences, but Bob wins once Dave and Carol go
+    expect(winnerEvent.winningOption).to.equal(1);
+    expect(winnerEvent.tie).to.equal(false);
+    expect(
+      winnerEvent.rounds.map((round) => round.map((c) => c.toNumber()))
+    ).to.deep.equal([
+      [2, 1, 1, 1],
+      [2, 2, 1, 0],
+      [2, 3, 0, 0],
+    ]);
+
+    const poll = await program.account.pollAccount.fetch(
+      pollPda(owner.publicKey, POLL_ID)
+    );
+    expect(poll.status).to.deep.equal({ finalized: {} });
+    expect(poll.result.winningOption).to.equal(1);
+  });
+
+  it("lets voters change their vote while the poll is open", async () => {
+    const POLL_ID = 480;
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
       provider as anchor.AnchorProvider,
-      "reveal_result",
+      program.programId
+    );
+
+    await initChangeVoteCompDef(program, owner);
+    await createPoll(
+      POLL_ID,
+      ["Yes", "No"],
+      { fullTally: {} },
+      null,
+      null,
+      { singleChoice: {} },
+      true
+    );
+
+    const [alice, bob] = [0, 1].map(() => anchor.web3.Keypair.generate());
+    for (const voter of [alice, bob]) {
+      await airdrop(voter.publicKey);
+    }
+    await castVote(alice, mxePublicKey, owner.publicKey, POLL_ID, 0);
+    await castVote(bob, mxePublicKey, owner.publicKey, POLL_ID, 1);
+
+    // Alice switches to "No" and back again, Bob switches to "Yes"
+    await changeVote(alice, mxePublicKey, owner.publicKey, POLL_ID, 1);
+    await changeVote(alice, mxePublicKey, owner.publicKey, POLL_ID, 0);
+    await changeVote(bob, mxePublicKey, owner.publicKey, POLL_ID, 0);
+
+    await closePoll(POLL_ID);
+    try {
+      await changeVote(bob, mxePublicKey, owner.publicKey, POLL_ID, 1);
+      expect.fail("Votes shouldn't change once the poll is closed");
+    } catch (error) {
+      expect(error.message).to.include("VotingClosed");
+    }
+
+    const tallyEvent = await revealTally(POLL_ID);
+    expect(
+      tallyEvent.counts.slice(0, 2).map((c) => c.toNumber())
+    ).to.deep.equal([2, 0]);
+    expect(tallyEvent.turnout.toNumber()).to.equal(2);
+  });
+
+  it("counts delegated votes with the delegate's ballot", async () => {
+    const POLL_ID = 490;
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+
+    await createPoll(POLL_ID, ["Yes", "No"], { fullTally: {} });
+    const poll = pollPda(owner.publicKey, POLL_ID);
+
+    const [delegate, forAllPolls, forThisPoll, independent] = Array.from(
+      { length: 4 },
+      () => anchor.web3.Keypair.generate()
+    );
+    for (const voter of [delegate, forAllPolls, forThisPoll, independent]) {
+      await airdrop(voter.publicKey);
+    }
+
+    // One delegation covers all the authority's polls, the other just this one
+    const delegations: [anchor.web3.Keypair, PublicKey][] = [
+      [forAllPolls, owner.publicKey],
+      [forThisPoll, poll],
+    ];
+    for (const [delegator, scope] of delegations) {
+      await program.methods
+        .delegateVote(scope, delegate.publicKey)
+        .accountsPartial({ delegator: delegator.publicKey })
+        .signers([delegator])
+        .rpc({ commitment: "confirmed" });
+    }
+
+    await castVote(
+      delegate,
+      mxePublicKey,
+      owner.publicKey,
+      POLL_ID,
+      0,
+      null,
+      [],
+      delegations.flatMap(([delegator, scope]) => [
+        {
+          pubkey: delegationPda(scope, delegator.publicKey),
+          isSigner: false,
+          isWritable: false,
+        },
+        {
+          pubkey: voterRecordPda(poll, delegator.publicKey),
+          isSigner: false,
+          isWritable: true,
+        },
+      ])
+    );
+    const delegatorRecord = await program.account.voterRecord.fetch(
+      voterRecordPda(poll, forAllPolls.publicKey)
+    );
+    expect(delegatorRecord.delegatedTo.toBase58()).to.equal(
+      delegate.publicKey.toBase58()
+    );
+
+    // A counted delegator calling vote is pointed to change_vote
+    try {
+      await castVote(forThisPoll, mxePublicKey, owner.publicKey, POLL_ID, 1);
+      expect.fail("A delegated voter should have been sent to change_vote");
+    } catch (error) {
+      expect(error.message).to.include("VoteDelegated");
+    }
+
+    // Voting directly overrides the delegation, even without vote changes
+    await changeVote(
+      forThisPoll,
+      mxePublicKey,
+      owner.publicKey,
+      POLL_ID,
+      1,
+      null,
+      delegate.publicKey
+    );
+    await castVote(independent, mxePublicKey, owner.publicKey, POLL_ID, 1);
+
+    await closePoll(POLL_ID);
+    const tallyEvent = await revealTally(POLL_ID);
+    expect(
+      tallyEvent.counts.slice(0, 2).map((c) => c.toNumber())
+    ).to.deep.equal([2, 2]);
+    expect(tallyEvent.turnout.toNumber()).to.equal(4);
+  });
+
+  async function revealTally(pollId: number) {
+    const tallyEventPromise = awaitEvent("revealTallyEvent");
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .revealTally(computationOffset, pollId)
+      .accountsPartial({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        clusterAccount: clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("reveal_tally")).readUInt32LE()
+        ),
+      })
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+    return tallyEventPromise;
+  }
+
+  async function initRankedTally(pollId: number) {
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .initRankedTally(computationOffset, pollId)
+      .accountsPartial({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        clusterAccount: clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("init_ranked_stats")).readUInt32LE()
+        ),
+      })
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+  }
+
+  async function revealIrvWinner(pollId: number) {
+    const winnerEventPromise = awaitEvent("revealIrvWinnerEvent");
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .revealIrvWinner(computationOffset, pollId)
+      .accountsPartial({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        clusterAccount: clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("reveal_irv_winner")).readUInt32LE()
+        ),
+      })
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+    return winnerEventPromise;
+  }
+
+  /** Voting window opening a minute ago and closing in an hour. */
+  function votingWindow(): [anchor.BN, anchor.BN] {
+    const now = Math.floor(Date.now() / 1000);
+    return [new anchor.BN(now - 60), new anchor.BN(now + 3600)];
+  }
+
+  async function closePoll(pollId: number) {
+    const authority = (provider as anchor.AnchorProvider).wallet.publicKey;
+    await program.methods
+      .closePoll(pollId)
+      .accountsPartial({ authority, pollAcc: pollPda(authority, pollId) })
+      .rpc({ commitment: "confirmed" });
+  }
+
+  async function queueRevealResult(pollId: number) {
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .revealResult(computationOffset, pollId)
+      .accountsPartial({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        clusterAccount: clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("reveal_result")).readUInt32LE()
+        ),
+      })
+      .rpc({ commitment: "confirmed" });
+  }
+
+  function pollPda(authority: PublicKey, pollId: number): PublicKey {
+    const pollIdBuffer = Buffer.alloc(4);
+    pollIdBuffer.writeUInt32LE(pollId);
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("poll"), authority.toBuffer(), pollIdBuffer],
+      program.programId
+    )[0];
+  }
+
+  function voterRecordPda(poll: PublicKey, voter: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("voter"), poll.toBuffer(), voter.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  function escrowPda(poll: PublicKey, voter: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), poll.toBuffer(), voter.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  function delegationPda(scope: PublicKey, delegator: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("delegation"), scope.toBuffer(), delegator.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  async function airdrop(pubkey: PublicKey) {
+    const sig = await provider.connection.requestAirdrop(
+      pubkey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+    const latest = await provider.connection.getLatestBlockhash();
+    await provider.connection.confirmTransaction({ signature: sig, ...latest });
+  }
+
+  async function createPoll(
+    pollId: number,
+    options: string[],
+    disclosure: anchor.IdlTypes<Voting>["disclosure"],
+    weightMint: PublicKey | null = null,
+    eligibilityRoot: Buffer | null = null,
+    method: anchor.IdlTypes<Voting>["votingMethod"] = { singleChoice: {} },
+    allowVoteChanges = false
+  ) {
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .createNewPoll(
+        computationOffset,
+        pollId,
+        `Poll ${pollId}`,
+        options,
+        disclosure,
+        ...votingWindow(),
+        weightMint,
+        eligibilityRoot && Array.from(eligibilityRoot),
+        method,
+        allowVoteChanges
+      )
+      .accountsPartial({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        clusterAccount: clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("init_vote_stats")).readUInt32LE()
+        ),
+      })
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+  }
+
+  async function lockTokens(
+    voter: anchor.web3.Keypair,
+    authority: PublicKey,
+    pollId: number,
+    weightMint: PublicKey,
+    voterTokenAccount: PublicKey,
+    amount: number
+  ) {
+    await program.methods
+      .lockTokens(pollId, new anchor.BN(amount))
+      .accountsPartial({
+        voter: voter.publicKey,
+        authority,
+        pollAcc: pollPda(authority, pollId),
+        weightMint,
+        voterTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([voter])
+      .rpc({ commitment: "confirmed" });
+  }
+
+  async function withdrawTokens(
+    voter: anchor.web3.Keypair,
+    authority: PublicKey,
+    pollId: number,
+    weightMint: PublicKey,
+    voterTokenAccount: PublicKey
+  ) {
+    await program.methods
+      .withdrawTokens(pollId)
+      .accountsPartial({
+        voter: voter.publicKey,
+        authority,
+        pollAcc: pollPda(authority, pollId),
+        weightMint,
+        voterTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([voter])
+      .rpc({ commitment: "confirmed" });
+  }
+
+  async function castVote(
+    voter: anchor.web3.Keypair,
+    mxePublicKey: Uint8Array,
+    authority: PublicKey,
+    pollId: number,
+    option: number,
+    voterEscrow: PublicKey | null = null,
+    eligibilityProof: Buffer[] = [],
+    delegations: anchor.web3.AccountMeta[] = []
+  ) {
+    const privateKey = x25519.utils.randomSecretKey();
+    const publicKey = x25519.getPublicKey(privateKey);
+    const cipher = new RescueCipher(
+      x25519.getSharedSecret(privateKey, mxePublicKey)
+    );
+    const nonce = randomBytes(16);
+    const ciphertext = cipher.encrypt([BigInt(option)], nonce);
+
+    const poll = pollPda(authority, pollId);
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .vote(
+        computationOffset,
+        pollId,
+        Array.from(ciphertext[0]),
+        Array.from(publicKey),
+        new anchor.BN(deserializeLE(nonce).toString()),
+        eligibilityProof.map((node) => Array.from(node))
+      )
+      .accountsPartial({
+        payer: voter.publicKey,
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        clusterAccount: clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("vote")).readUInt32LE()
+        ),
+        authority,
+        pollAcc: poll,
+        voterRecord: voterRecordPda(poll, voter.publicKey),
+        voterEscrow,
+      })
+      .remainingAccounts(delegations)
+      .signers([voter])
+      .rpc({ commitment: "confirmed" });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+  }
+
+  async function changeVote(
+    voter: anchor.web3.Keypair,
+    mxePublicKey: Uint8Array,
+    authority: PublicKey,
+    pollId: number,
+    option: number,
+    voterEscrow: PublicKey | null = null,
+    delegate: PublicKey | null = null
+  ) {
+    const privateKey = x25519.utils.randomSecretKey();
+    const publicKey = x25519.getPublicKey(privateKey);
+    const cipher = new RescueCipher(
+      x25519.getSharedSecret(privateKey, mxePublicKey)
+    );
+    const nonce = randomBytes(16);
+    const ciphertext = cipher.encrypt([BigInt(option)], nonce);
+
+    const poll = pollPda(authority, pollId);
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .changeVote(
+        computationOffset,
+        pollId,
+        Array.from(ciphertext[0]),
+        Array.from(publicKey),
+        new anchor.BN(deserializeLE(nonce).toString())
+      )
+      .accountsPartial({
+        payer: voter.publicKey,
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        clusterAccount: clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("change_vote")).readUInt32LE()
+        ),
+        authority,
+        pollAcc: poll,
+        voterRecord: voterRecordPda(poll, voter.publicKey),
+        voterEscrow,
+        delegateRecord: delegate && voterRecordPda(poll, delegate),
+      })
+      .signers([voter])
+      .rpc({ commitment: "confirmed" });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+  }
+
+  async function castQuadraticVote(
+    voter: anchor.web3.Keypair,
+    mxePublicKey: Uint8Array,
+    authority: PublicKey,
+    pollId: number,
+    votes: number[],
+    voterEscrow: PublicKey | null = null,
+    eligibilityProof: Buffer[] = []
+  ) {
+    const privateKey = x25519.utils.randomSecretKey();
+    const publicKey = x25519.getPublicKey(privateKey);
+    const cipher = new RescueCipher(
+      x25519.getSharedSecret(privateKey, mxePublicKey)
+    );
+    const nonce = randomBytes(16);
+    const ciphertext = cipher.encrypt(votes.map(BigInt), nonce);
+
+    const poll = pollPda(authority, pollId);
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .voteQuadratic(
+        computationOffset,
+        pollId,
+        ciphertext.map((c) => Array.from(c)),
+        Array.from(publicKey),
+        new anchor.BN(deserializeLE(nonce).toString()),
+        eligibilityProof.map((node) => Array.from(node))
+      )
+      .accountsPartial({
+        payer: voter.publicKey,
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        clusterAccount: clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("vote_quadratic")).readUInt32LE()
+        ),
+        authority,
+        pollAcc: poll,
+        voterRecord: voterRecordPda(poll, voter.publicKey),
+        voterEscrow,
+      })
+      .signers([voter])
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+  }
+
+  async function castRankedVote(
+    voter: anchor.web3.Keypair,
+    mxePublicKey: Uint8Array,
+    authority: PublicKey,
+    pollId: number,
+    ranking: number[],
+    voterEscrow: PublicKey | null = null,
+    eligibilityProof: Buffer[] = []
+  ) {
+    const privateKey = x25519.utils.randomSecretKey();
+    const publicKey = x25519.getPublicKey(privateKey);
+    const cipher = new RescueCipher(
+      x25519.getSharedSecret(privateKey, mxePublicKey)
+    );
+    const nonce = randomBytes(16);
+    const ciphertext = cipher.encrypt(ranking.map(BigInt), nonce);
+
+    const poll = pollPda(authority, pollId);
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await program.methods
+      .voteRanked(
+        computationOffset,
+        pollId,
+        ciphertext.map((c) => Array.from(c)),
+        Array.from(publicKey),
+        new anchor.BN(deserializeLE(nonce).toString()),
+        eligibilityProof.map((node) => Array.from(node))
+      )
+      .accountsPartial({
+        payer: voter.publicKey,
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          computationOffset
+        ),
+        clusterAccount: clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(
+          arciumEnv.arciumClusterOffset
+        ),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("vote_ranked")).readUInt32LE()
+        ),
+        authority,
+        pollAcc: poll,
+        voterRecord: voterRecordPda(poll, voter.publicKey),
+        voterEscrow,
+      })
+      .signers([voter])
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+  }
+
+  async function initVoteStatsCompDef(
+    program: Program<Voting>,
+    owner: anchor.web3.Keypair
+  ): Promise<string> {
+    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+      "ComputationDefinitionAccount"
+    );
+    const offset = getCompDefAccOffset("init_vote_stats");
+
+    const compDefPDA = PublicKey.findProgramAddressSync(
+      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+      getArciumProgramId()
+    )[0];
+
+    console.log(
+      "Init vote stats computation definition pda is ",
+      compDefPDA.toBase58()
+    );
+
+    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
+    const mxeAccount = getMXEAccAddress(program.programId);
+    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+    const lutAddress = getLookupTableAddress(
+      program.programId,
+      mxeAcc.lutOffsetSlot
+    );
+
+    const sig = await program.methods
+      .initVoteStatsCompDef()
+      .accounts({
+        compDefAccount: compDefPDA,
+        payer: owner.publicKey,
+        mxeAccount,
+        addressLookupTable: lutAddress,
+      })
+      .signers([owner])
+      .rpc({
+        preflightCommitment: "confirmed",
+        commitment: "confirmed",
+      });
+    console.log("Init vote stats computation definition transaction", sig);
+
+    const rawCircuit = fs.readFileSync("build/init_vote_stats.arcis");
+    await uploadCircuit(
+      provider as anchor.AnchorProvider,
+      "init_vote_stats",
+      program.programId,
+      rawCircuit,
+      true
+    );
+
+    return sig;
+  }
+
+  async function initVoteCompDef(
+    program: Program<Voting>,
+    owner: anchor.web3.Keypair
+  ): Promise<string> {
+    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+      "ComputationDefinitionAccount"
+    );
+    const offset = getCompDefAccOffset("vote");
+
+    const compDefPDA = PublicKey.findProgramAddressSync(
+      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+      getArciumProgramId()
+    )[0];
+
+    console.log("Vote computation definition pda is ", compDefPDA.toBase58());
+
+    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
+    const mxeAccount = getMXEAccAddress(program.programId);
+    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+    const lutAddress = getLookupTableAddress(
+      program.programId,
+      mxeAcc.lutOffsetSlot
+    );
+
+    const sig = await program.methods
+      .initVoteCompDef()
+      .accounts({
+        compDefAccount: compDefPDA,
+        payer: owner.publicKey,
+        mxeAccount,
+        addressLookupTable: lutAddress,
+      })
+      .signers([owner])
+      .rpc({
+        preflightCommitment: "confirmed",
+        commitment: "confirmed",
+      });
+    console.log("Init vote computation definition transaction", sig);
+
+    const rawCircuit = fs.readFileSync("build/vote.arcis");
+    await uploadCircuit(
+      provider as anchor.AnchorProvider,
+      "vote",
+      program.programId,
+      rawCircuit,
+      true
+    );
+
+    return sig;
+  }
+
+  async function initChangeVoteCompDef(
+    program: Program<Voting>,
+    owner: anchor.web3.Keypair
+  ): Promise<string> {
+    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+      "ComputationDefinitionAccount"
+    );
+    const offset = getCompDefAccOffset("change_vote");
+
+    const compDefPDA = PublicKey.findProgramAddressSync(
+      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+      getArciumProgramId()
+    )[0];
+
+    console.log(
+      "Change vote computation definition pda is ",
+      compDefPDA.toBase58()
+    );
+
+    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
+    const mxeAccount = getMXEAccAddress(program.programId);
+    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+    const lutAddress = getLookupTableAddress(
+      program.programId,
+      mxeAcc.lutOffsetSlot
+    );
+
+    const sig = await program.methods
+      .initChangeVoteCompDef()
+      .accounts({
+        compDefAccount: compDefPDA,
+        payer: owner.publicKey,
+        mxeAccount,
+        addressLookupTable: lutAddress,
+      })
+      .signers([owner])
+      .rpc({
+        preflightCommitment: "confirmed",
+        commitment: "confirmed",
+      });
+    console.log("Init change vote computation definition transaction", sig);
+
+    const rawCircuit = fs.readFileSync("build/change_vote.arcis");
+    await uploadCircuit(
+      provider as anchor.AnchorProvider,
+      "change_vote",
+      program.programId,
+      rawCircuit,
+      true
+    );
+
+    return sig;
+  }
+
+  async function initVoteQuadraticCompDef(
+    program: Program<Voting>,
+    owner: anchor.web3.Keypair
+  ): Promise<string> {
+    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+      "ComputationDefinitionAccount"
+    );
+    const offset = getCompDefAccOffset("vote_quadratic");
+
+    const compDefPDA = PublicKey.findProgramAddressSync(
+      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+      getArciumProgramId()
+    )[0];
+
+    console.log(
+      "Vote quadratic computation definition pda is ",
+      compDefPDA.toBase58()
+    );
+
+    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
+    const mxeAccount = getMXEAccAddress(program.programId);
+    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+    const lutAddress = getLookupTableAddress(
+      program.programId,
+      mxeAcc.lutOffsetSlot
+    );
+
+    const sig = await program.methods
+      .initVoteQuadraticCompDef()
+      .accounts({
+        compDefAccount: compDefPDA,
+        payer: owner.publicKey,
+        mxeAccount,
+        addressLookupTable: lutAddress,
+      })
+      .signers([owner])
+      .rpc({
+        preflightCommitment: "confirmed",
+        commitment: "confirmed",
+      });
+    console.log("Init vote quadratic computation definition transaction", sig);
+
+    const rawCircuit = fs.readFileSync("build/vote_quadratic.arcis");
+    await uploadCircuit(
+      provider as anchor.AnchorProvider,
+      "vote_quadratic",
+      program.programId,
+      rawCircuit,
+      true
+    );
+
+    return sig;
+  }
+
+  async function initRankedStatsCompDef(
+    program: Program<Voting>,
+    owner: anchor.web3.Keypair
+  ): Promise<string> {
+    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+      "ComputationDefinitionAccount"
+    );
+    const offset = getCompDefAccOffset("init_ranked_stats");
+
+    const compDefPDA = PublicKey.findProgramAddressSync(
+      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+      getArciumProgramId()
+    )[0];
+
+    console.log(
+      "Init ranked stats computation definition pda is ",
+      compDefPDA.toBase58()
+    );
+
+    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
+    const mxeAccount = getMXEAccAddress(program.programId);
+    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+    const lutAddress = getLookupTableAddress(
+      program.programId,
+      mxeAcc.lutOffsetSlot
+    );
+
+    const sig = await program.methods
+      .initRankedStatsCompDef()
+      .accounts({
+        compDefAccount: compDefPDA,
+        payer: owner.publicKey,
+        mxeAccount,
+        addressLookupTable: lutAddress,
+      })
+      .signers([owner])
+      .rpc({
+        preflightCommitment: "confirmed",
+        commitment: "confirmed",
+      });
+    console.log("Init ranked stats computation definition transaction", sig);
+
+    const rawCircuit = fs.readFileSync("build/init_ranked_stats.arcis");
+    await uploadCircuit(
+      provider as anchor.AnchorProvider,
+      "init_ranked_stats",
+      program.programId,
+      rawCircuit,
+      true
+    );
+
+    return sig;
+  }
+
+  async function initVoteRankedCompDef(
+    program: Program<Voting>,
+    owner: anchor.web3.Keypair
+  ): Promise<string> {
+    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+      "ComputationDefinitionAccount"
+    );
+    const offset = getCompDefAccOffset("vote_ranked");
+
+    const compDefPDA = PublicKey.findProgramAddressSync(
+      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+      getArciumProgramId()
+    )[0];
+
+    console.log(
+      "Vote ranked computation definition pda is ",
+      compDefPDA.toBase58()
+    );
+
+    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
+    const mxeAccount = getMXEAccAddress(program.programId);
+    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+    const lutAddress = getLookupTableAddress(
+      program.programId,
+      mxeAcc.lutOffsetSlot
+    );
+
+    const sig = await program.methods
+      .initVoteRankedCompDef()
+      .accounts({
+        compDefAccount: compDefPDA,
+        payer: owner.publicKey,
+        mxeAccount,
+        addressLookupTable: lutAddress,
+      })
+      .signers([owner])
+      .rpc({
+        preflightCommitment: "confirmed",
+        commitment: "confirmed",
+      });
+    console.log("Init vote ranked computation definition transaction", sig);
+
+    const rawCircuit = fs.readFileSync("build/vote_ranked.arcis");
+    await uploadCircuit(
+      provider as anchor.AnchorProvider,
+      "vote_ranked",
+      program.programId,
+      rawCircuit,
+      true
+    );
+
+    return sig;
+  }
+
+  async function initRevealIrvWinnerCompDef(
+    program: Program<Voting>,
+    owner: anchor.web3.Keypair
+  ): Promise<string> {
+    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+      "ComputationDefinitionAccount"
+    );
+    const offset = getCompDefAccOffset("reveal_irv_winner");
+
+    const compDefPDA = PublicKey.findProgramAddressSync(
+      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+      getArciumProgramId()
+    )[0];
+
+    console.log(
+      "Reveal IRV winner computation definition pda is ",
+      compDefPDA.toBase58()
+    );
+
+    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
+    const mxeAccount = getMXEAccAddress(program.programId);
+    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+    const lutAddress = getLookupTableAddress(
+      program.programId,
+      mxeAcc.lutOffsetSlot
+    );
+
+    const sig = await program.methods
+      .initRevealIrvWinnerCompDef()
+      .accounts({
+        compDefAccount: compDefPDA,
+        payer: owner.publicKey,
+        mxeAccount,
+        addressLookupTable: lutAddress,
+      })
+      .signers([owner])
+      .rpc({
+        preflightCommitment: "confirmed",
+        commitment: "confirmed",
+      });
+    console.log(
+      "Init reveal IRV winner computation definition transaction",
+      sig
+    );
+
+    const rawCircuit = fs.readFileSync("build/reveal_irv_winner.arcis");
+    await uploadCircuit(
+      provider as anchor.AnchorProvider,
+      "reveal_irv_winner",
+      program.programId,
+      rawCircuit,
+      true
+    );
+
+    return sig;
+  }
+
+  async function initRevealResultCompDef(
+    program: Program<Voting>,
+    owner: anchor.web3.Keypair
+  ): Promise<string> {
+    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+      "ComputationDefinitionAccount"
+    );
+    const offset = getCompDefAccOffset("reveal_result");
+
+    const compDefPDA = PublicKey.findProgramAddressSync(
+      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+      getArciumProgramId()
+    )[0];
+
+    console.log(
+      "Reveal result computation definition pda is ",
+      compDefPDA.toBase58()
+    );
+
+    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
+    const mxeAccount = getMXEAccAddress(program.programId);
+    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+    const lutAddress = getLookupTableAddress(
+      program.programId,
+      mxeAcc.lutOffsetSlot
+    );
+
+    const sig = await program.methods
+      .initRevealResultCompDef()
+      .accounts({
+        compDefAccount: compDefPDA,
+        payer: owner.publicKey,
+        mxeAccount,
+        addressLookupTable: lutAddress,
+      })
+      .signers([owner])
+      .rpc({
+        preflightCommitment: "confirmed",
+        commitment: "confirmed",
+      });
+    console.log("Init reveal result computation definition transaction", sig);
+
+    const rawCircuit = fs.readFileSync("build/reveal_result.arcis");
+    await uploadCircuit(
+      provider as anchor.AnchorProvider,
+      "reveal_result",
+      program.programId,
+      rawCircuit,
+      true
+    );
+
+    return sig;
+  }
+  async function initRevealTallyCompDef(
+    program: Program<Voting>,
+    owner: anchor.web3.Keypair
+  ): Promise<string> {
+    const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+      "ComputationDefinitionAccount"
+    );
+    const offset = getCompDefAccOffset("reveal_tally");
+
+    const compDefPDA = PublicKey.findProgramAddressSync(
+      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+      getArciumProgramId()
+    )[0];
+
+    console.log(
+      "Reveal tally computation definition pda is ",
+      compDefPDA.toBase58()
+    );
+
+    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
+    const mxeAccount = getMXEAccAddress(program.programId);
+    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+    const lutAddress = getLookupTableAddress(
+      program.programId,
+      mxeAcc.lutOffsetSlot
+    );
+
+    const sig = await program.methods
+      .initRevealTallyCompDef()
+      .accounts({
+        compDefAccount: compDefPDA,
+        payer: owner.publicKey,
+        mxeAccount,
+        addressLookupTable: lutAddress,
+      })
+      .signers([owner])
+      .rpc({
+        preflightCommitment: "confirmed",
+        commitment: "confirmed",
+      });
+    console.log("Init reveal tally computation definition transaction", sig);
+
+    const rawCircuit = fs.readFileSync("build/reveal_tally.arcis");
+    await uploadCircuit(
+      provider as anchor.AnchorProvider,
+      "reveal_tally",
       program.programId,
       rawCircuit,
       true